@@ -2,7 +2,6 @@ use easy_db::{EasyClient, EasyDB};
 use serde_json::json;
 use std::collections::HashMap;
 use std::time::Duration;
-use tokio::time::sleep;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -35,13 +34,12 @@ async fn main() -> anyhow::Result<()> {
         }
     });
 
-    // Give the server a moment to start up
-    sleep(Duration::from_millis(500)).await;
-
     // =====================================================
     // PART 2: CLIENT OPERATIONS (Main Thread)
     // =====================================================
     let client = EasyClient::new("localhost", port);
+    // Wait until the server answers its health check
+    client.wait_until_ready(Duration::from_secs(5)).await?;
     println!("\n🔗 [CLIENT] Connected to localhost:{}\n", port);
 
     // --- SCENARIO 1: CREATE (POST) ---
@@ -0,0 +1,931 @@
+//! The HTTP client for an `EasyDB` server (feature `client`).
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::time::Duration;
+
+/// Client Structure: Allows users to easily connect to the server
+pub struct EasyClient {
+    pub base_url: String,
+    settings: Settings,
+    client: reqwest::Client,
+}
+
+/// Delay before the first retry; doubled after every failed attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(100);
+
+/// Delay between two pings of `wait_until_ready`
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+impl EasyClient {
+    /// Creates a new client (e.g., localhost, 9000)
+    pub fn new(host: &str, port: u16) -> Self {
+        Self {
+            base_url: format!("http://{}:{}", host, port),
+            settings: Settings::default(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Sends the given API key with every request (`X-API-Key` header)
+    pub fn with_api_key(mut self, key: &str) -> Self {
+        self.settings.api_key = Some(key.to_string());
+        self
+    }
+
+    /// Talks to a server mounted under a prefix (see `EasyDB::with_prefix`)
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.base_url.push_str(prefix.trim_end_matches('/'));
+        self
+    }
+
+    /// Uses an existing `reqwest::Client`, e.g. to share one connection pool
+    /// between several `EasyClient`s. Every request goes through this client.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Fails any request that takes longer than `timeout` (connecting included)
+    /// (replaces any client set with `with_client`)
+    pub fn with_timeout(mut self, timeout: Duration) -> anyhow::Result<Self> {
+        self.client = reqwest::Client::builder().timeout(timeout).build()?;
+        Ok(self)
+    }
+
+    /// Retries idempotent requests (GET, PUT, DELETE) up to `retries` times on
+    /// connection errors, timeouts and `5xx` responses, with exponential backoff
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.settings.retries = retries;
+        self
+    }
+
+    /// Helper: Builds calls from this client's base URL and settings
+    fn calls(&self) -> Calls<'_> {
+        Calls {
+            base_url: &self.base_url,
+            settings: &self.settings,
+        }
+    }
+
+    /// Sends a call and parses the JSON response body
+    async fn send(&self, call: Call) -> anyhow::Result<Value> {
+        let res = self.send_raw(&call).await?;
+        let status = res.status();
+        json_body(status, &res.text().await?)
+    }
+
+    /// Sends a call, retrying it as often as it allows
+    async fn send_raw(&self, call: &Call) -> Result<reqwest::Response, reqwest::Error> {
+        let mut delay = RETRY_BASE_DELAY;
+        for attempt in 0..=call.retries {
+            let mut req = self
+                .client
+                .request(call.method.clone(), &call.url)
+                .headers(call.headers.clone());
+            if let Some(body) = &call.body {
+                req = req.body(body.clone());
+            }
+            let res = req.send().await;
+            if attempt == call.retries || !should_retry(res.as_ref().map(|r| r.status())) {
+                return res;
+            }
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+        unreachable!("the last attempt always returns")
+    }
+
+    /// Sends a GET request (Supports Filtering and Sorting)
+    pub async fn get(
+        &self,
+        table: &str,
+        params: Option<HashMap<&str, &str>>,
+    ) -> anyhow::Result<Value> {
+        self.send(self.calls().list(table, params)?).await
+    }
+
+    /// Like `get`, but also returns the pagination headers. Pass `_page` /
+    /// `_per_page` (or `_limit` / `_offset`) in `params`.
+    pub async fn get_page(
+        &self,
+        table: &str,
+        params: Option<HashMap<&str, &str>>,
+    ) -> anyhow::Result<Page> {
+        let res = self.send_raw(&self.calls().list(table, params)?).await?;
+        let (status, headers) = (res.status(), res.headers().clone());
+        Page::from_parts(&headers, json_body(status, &res.text().await?)?)
+    }
+
+    /// Sends a GET request built with `Query` (filters, sorting, pagination)
+    pub async fn query(&self, table: &str, query: &Query) -> anyhow::Result<Value> {
+        self.send(self.calls().query(table, query)?).await
+    }
+
+    /// Like `query`, but deserializes the rows into `T`
+    pub async fn query_as<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        query: &Query,
+    ) -> anyhow::Result<Vec<T>> {
+        from_response(self.query(table, query).await?)
+    }
+
+    /// Like `get`, but deserializes the rows into `T`
+    pub async fn get_as<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        params: Option<HashMap<&str, &str>>,
+    ) -> anyhow::Result<Vec<T>> {
+        from_response(self.get(table, params).await?)
+    }
+
+    /// Fetches a single record by id (Supports `_expand` / `_embed`)
+    pub async fn get_by_id(
+        &self,
+        table: &str,
+        id: impl std::fmt::Display,
+        params: Option<HashMap<&str, &str>>,
+    ) -> anyhow::Result<Value> {
+        self.get(&format!("{}/{}", table, id), params).await
+    }
+
+    /// Like `get_by_id`, but deserializes the record into `T`
+    pub async fn get_by_id_as<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        id: impl std::fmt::Display,
+        params: Option<HashMap<&str, &str>>,
+    ) -> anyhow::Result<T> {
+        from_response(self.get_by_id(table, id, params).await?)
+    }
+
+    /// Fetches the records with the given ids, in the order the ids were given.
+    /// Ids that don't exist are skipped.
+    pub async fn get_many(&self, table: &str, ids: &[i64]) -> anyhow::Result<Vec<Value>> {
+        self.get_many_by(table, "id", ids).await
+    }
+
+    /// Like `get_many`, for a table addressed by another key column (see
+    /// `EasyDB::primary_key`)
+    pub async fn get_many_by(
+        &self,
+        table: &str,
+        key: &str,
+        ids: &[impl std::fmt::Display],
+    ) -> anyhow::Result<Vec<Value>> {
+        let ids = key_strings(ids);
+        let res = self.send(self.calls().many(table, key, &ids)?).await?;
+        order_by_ids(res, key, &ids)
+    }
+
+    /// Sends a POST request (Create Data). Accepts a `Value` or any `Serialize` type
+    pub async fn post<T: Serialize>(&self, table: &str, data: T) -> anyhow::Result<Value> {
+        self.send(self.calls().post(table, data)?).await
+    }
+
+    /// Sends an UPSERT request (Create or Update Data by a unique column)
+    pub async fn upsert<T: Serialize>(
+        &self,
+        table: &str,
+        conflict: &str,
+        data: T,
+    ) -> anyhow::Result<Value> {
+        self.send(self.calls().upsert(table, conflict, data)?).await
+    }
+
+    /// Sends a PUT request (Update Data)
+    pub async fn put<T: Serialize>(
+        &self,
+        table: &str,
+        id: impl std::fmt::Display,
+        data: T,
+    ) -> anyhow::Result<Value> {
+        self.send(self.calls().put(table, id, data)?).await
+    }
+
+    /// Sends a PATCH request with JSON merge-patch semantics: `null` clears a column
+    pub async fn patch<T: Serialize>(
+        &self,
+        table: &str,
+        id: impl std::fmt::Display,
+        data: T,
+    ) -> anyhow::Result<Value> {
+        self.send(self.calls().patch(table, id, data)?).await
+    }
+
+    /// Sends a DELETE request (Delete Data)
+    pub async fn delete(&self, table: &str, id: impl std::fmt::Display) -> anyhow::Result<Value> {
+        self.send(self.calls().delete(table, id)?).await
+    }
+
+    /// Sends any request to `path` (e.g. `students/5` or `students?age_gt=18`)
+    /// and returns the status with the body, whatever the status. Use it to
+    /// tell a `404` from a `200` without inspecting the body.
+    pub async fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> anyhow::Result<ApiResponse> {
+        let res = self
+            .send_raw(&self.calls().request(method, path, body)?)
+            .await?;
+        let status = res.status();
+        Ok(ApiResponse::from_parts(status, &res.text().await?))
+    }
+
+    /// Whether the server answers `GET /_health` with `200` (within the
+    /// timeout, if one is set). An unreachable server is `Ok(false)`, not an error.
+    pub async fn ping(&self) -> anyhow::Result<bool> {
+        let res = self.send_raw(&self.calls().health()?).await;
+        ping_result(res.map(|r| r.status()))
+    }
+
+    /// Pings the server until it is up, e.g. right after spawning it;
+    /// fails if it still isn't after `timeout`
+    pub async fn wait_until_ready(&self, timeout: Duration) -> anyhow::Result<()> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while !self.ping().await? {
+            if tokio::time::Instant::now() >= deadline {
+                anyhow::bail!("Server at {} not ready after {:?}", self.base_url, timeout);
+            }
+            tokio::time::sleep(READY_POLL_INTERVAL).await;
+        }
+        Ok(())
+    }
+}
+
+/// Sort direction for `Query::sort`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+/// Builds the query string of a listing without knowing the server's
+/// parameter conventions; values are URL-encoded. Conditions are AND-ed:
+///
+/// `Query::new().eq("name", "Bob").gt("age", 18).sort("gpa", Order::Desc).limit(10)`
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    params: Vec<(String, String)>,
+}
+
+impl Query {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a raw parameter, for anything without a dedicated method
+    pub fn param(mut self, key: &str, value: impl std::fmt::Display) -> Self {
+        self.params.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// `column = value`
+    pub fn eq(self, column: &str, value: impl std::fmt::Display) -> Self {
+        self.param(column, value)
+    }
+
+    /// `column = value`, ignoring ASCII case
+    pub fn eq_ci(self, column: &str, value: impl std::fmt::Display) -> Self {
+        self.param(&format!("{}_ci", column), value)
+    }
+
+    /// `column != value`
+    pub fn ne(self, column: &str, value: impl std::fmt::Display) -> Self {
+        self.param(&format!("{}_ne", column), value)
+    }
+
+    /// `column < value`
+    pub fn lt(self, column: &str, value: impl std::fmt::Display) -> Self {
+        self.param(&format!("{}_lt", column), value)
+    }
+
+    /// `column <= value`
+    pub fn lte(self, column: &str, value: impl std::fmt::Display) -> Self {
+        self.param(&format!("{}_lte", column), value)
+    }
+
+    /// `column > value`
+    pub fn gt(self, column: &str, value: impl std::fmt::Display) -> Self {
+        self.param(&format!("{}_gt", column), value)
+    }
+
+    /// `column >= value`
+    pub fn gte(self, column: &str, value: impl std::fmt::Display) -> Self {
+        self.param(&format!("{}_gte", column), value)
+    }
+
+    /// `column IN (values)`
+    pub fn any_of<V: std::fmt::Display>(self, column: &str, values: &[V]) -> Self {
+        let list: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        self.param(&format!("{}__in", column), list.join(","))
+    }
+
+    /// `column IS NULL`
+    pub fn is_null(self, column: &str) -> Self {
+        self.param(&format!("{}_is_null", column), true)
+    }
+
+    /// `column IS NOT NULL`
+    pub fn not_null(self, column: &str) -> Self {
+        self.param(&format!("{}_not_null", column), true)
+    }
+
+    /// Orders the rows by `column`
+    pub fn sort(self, column: &str, order: Order) -> Self {
+        let order = match order {
+            Order::Asc => "asc",
+            Order::Desc => "desc",
+        };
+        self.param("_sort", column).param("_order", order)
+    }
+
+    /// Returns at most `limit` rows
+    pub fn limit(self, limit: u64) -> Self {
+        self.param("_limit", limit)
+    }
+
+    /// Skips the first `offset` rows (use with `limit`)
+    pub fn offset(self, offset: u64) -> Self {
+        self.param("_offset", offset)
+    }
+
+    /// Returns page `page` (from 1) of `per_page` rows
+    pub fn page(self, page: u64, per_page: u64) -> Self {
+        self.param("_page", page).param("_per_page", per_page)
+    }
+
+    /// Returns only these columns
+    pub fn fields(self, columns: &[&str]) -> Self {
+        self.param("_fields", columns.join(","))
+    }
+
+    /// The encoded query string, without the leading `?`
+    pub fn to_query_string(&self) -> String {
+        let mut url = reqwest::Url::parse("http://localhost/").expect("static URL is valid");
+        url.query_pairs_mut().extend_pairs(&self.params);
+        url.query().unwrap_or_default().to_string()
+    }
+
+    /// Helper: `{base}/{table}?{query}`
+    fn url(&self, base_url: &str, table: &str) -> anyhow::Result<String> {
+        let mut url = reqwest::Url::parse(&format!("{}/{}", base_url, table))?;
+        if !self.params.is_empty() {
+            url.query_pairs_mut().extend_pairs(&self.params);
+        }
+        Ok(url.to_string())
+    }
+}
+
+/// One page of a listing, as returned by `EasyClient::get_page`
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub rows: Vec<Value>,
+    /// Rows matching the filters across all pages (`X-Total-Count`)
+    pub total: Option<u64>,
+    /// Path and query of the next page, from the `Link` header
+    pub next: Option<String>,
+    /// Path and query of the previous page, from the `Link` header
+    pub prev: Option<String>,
+}
+
+impl Page {
+    /// Helper: Builds a page from the listing's headers and body
+    fn from_parts(headers: &reqwest::header::HeaderMap, body: Value) -> anyhow::Result<Self> {
+        let total = headers
+            .get("x-total-count")
+            .and_then(|v| v.to_str().ok()?.parse().ok());
+        let links = headers
+            .get(reqwest::header::LINK)
+            .and_then(|v| v.to_str().ok())
+            .map(parse_links)
+            .unwrap_or_default();
+        Ok(Page {
+            rows: from_response(body)?,
+            total,
+            next: links.get("next").cloned(),
+            prev: links.get("prev").cloned(),
+        })
+    }
+}
+
+/// A response together with its HTTP status, as returned by `EasyClient::request`
+#[derive(Debug, Clone)]
+pub struct ApiResponse {
+    pub status: reqwest::StatusCode,
+    /// The JSON body; `null` when empty, a string when it isn't JSON (e.g. CSV)
+    pub body: Value,
+}
+
+impl ApiResponse {
+    /// Helper: Parses the body text, which may be empty or not JSON
+    fn from_parts(status: reqwest::StatusCode, text: &str) -> Self {
+        let body = if text.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+        };
+        ApiResponse { status, body }
+    }
+
+    /// Whether the status is `2xx`
+    pub fn is_success(&self) -> bool {
+        self.status.is_success()
+    }
+
+    /// The body of a `2xx` response; any other status becomes an error
+    /// carrying the status and the server's `error` message
+    pub fn into_result(self) -> anyhow::Result<Value> {
+        if self.is_success() {
+            return Ok(self.body);
+        }
+        match self.body.get("error") {
+            Some(error) => Err(anyhow::anyhow!("{}: {}", self.status, error)),
+            None => Err(anyhow::anyhow!("{}", self.status)),
+        }
+    }
+}
+
+/// Client-wide settings shared by `EasyClient` and `BlockingClient`
+#[derive(Default)]
+struct Settings {
+    api_key: Option<String>,
+    retries: u32,
+}
+
+/// One request as both clients send it: built by `Calls`, then sent (and
+/// retried) by whichever client made it
+struct Call {
+    method: reqwest::Method,
+    url: String,
+    headers: reqwest::header::HeaderMap,
+    body: Option<Vec<u8>>,
+    /// How often a failed attempt may be repeated
+    retries: u32,
+}
+
+/// Builds the calls of both clients from their base URL and settings, so
+/// the two differ only in how a call is sent
+struct Calls<'a> {
+    base_url: &'a str,
+    settings: &'a Settings,
+}
+
+impl Calls<'_> {
+    /// Helper: A call to `url` with the client-wide headers. Only idempotent
+    /// calls (GET, PUT, DELETE) are retried.
+    fn call(
+        &self,
+        method: reqwest::Method,
+        url: String,
+        body: Option<Value>,
+    ) -> anyhow::Result<Call> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        if let Some(key) = &self.settings.api_key {
+            headers.insert("X-API-Key", key.parse()?);
+        }
+        if body.is_some() {
+            let content_type = if method == reqwest::Method::PATCH {
+                "application/merge-patch+json"
+            } else {
+                "application/json"
+            };
+            headers.insert(
+                reqwest::header::CONTENT_TYPE,
+                reqwest::header::HeaderValue::from_static(content_type),
+            );
+        }
+        let idempotent = matches!(
+            method,
+            reqwest::Method::GET | reqwest::Method::PUT | reqwest::Method::DELETE
+        );
+        Ok(Call {
+            retries: if idempotent { self.settings.retries } else { 0 },
+            body: body.map(|body| body.to_string().into_bytes()),
+            method,
+            url,
+            headers,
+        })
+    }
+
+    /// GET `/{table}` with the filter parameters
+    fn list(&self, table: &str, params: Option<HashMap<&str, &str>>) -> anyhow::Result<Call> {
+        let url = list_url(self.base_url, table, params);
+        self.call(reqwest::Method::GET, url, None)
+    }
+
+    /// GET `/{table}` with the parameters of `query`
+    fn query(&self, table: &str, query: &Query) -> anyhow::Result<Call> {
+        let url = query.url(self.base_url, table)?;
+        self.call(reqwest::Method::GET, url, None)
+    }
+
+    /// GET `/{table}?{key}__in=...` for the given keys
+    fn many(&self, table: &str, key: &str, ids: &[String]) -> anyhow::Result<Call> {
+        let joined = ids.join(",");
+        let filter = format!("{}__in", key);
+        let mut params = HashMap::new();
+        params.insert(filter.as_str(), joined.as_str());
+        self.list(table, Some(params))
+    }
+
+    /// POST `/{table}`
+    fn post<T: Serialize>(&self, table: &str, data: T) -> anyhow::Result<Call> {
+        let url = format!("{}/{}", self.base_url, table);
+        self.call(
+            reqwest::Method::POST,
+            url,
+            Some(serde_json::to_value(data)?),
+        )
+    }
+
+    /// POST `/{table}/upsert?conflict={conflict}`
+    fn upsert<T: Serialize>(&self, table: &str, conflict: &str, data: T) -> anyhow::Result<Call> {
+        let url = format!("{}/{}/upsert?conflict={}", self.base_url, table, conflict);
+        self.call(
+            reqwest::Method::POST,
+            url,
+            Some(serde_json::to_value(data)?),
+        )
+    }
+
+    /// PUT `/{table}/{id}`
+    fn put<T: Serialize>(&self, table: &str, id: impl Display, data: T) -> anyhow::Result<Call> {
+        let url = format!("{}/{}/{}", self.base_url, table, id);
+        self.call(reqwest::Method::PUT, url, Some(serde_json::to_value(data)?))
+    }
+
+    /// PATCH `/{table}/{id}` with a merge patch
+    fn patch<T: Serialize>(&self, table: &str, id: impl Display, data: T) -> anyhow::Result<Call> {
+        let url = format!("{}/{}/{}", self.base_url, table, id);
+        self.call(
+            reqwest::Method::PATCH,
+            url,
+            Some(serde_json::to_value(data)?),
+        )
+    }
+
+    /// DELETE `/{table}/{id}`
+    fn delete(&self, table: &str, id: impl Display) -> anyhow::Result<Call> {
+        let url = format!("{}/{}/{}", self.base_url, table, id);
+        self.call(reqwest::Method::DELETE, url, None)
+    }
+
+    /// Any method on `path`, relative to the base URL
+    fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> anyhow::Result<Call> {
+        let url = format!("{}/{}", self.base_url, path.trim_start_matches('/'));
+        self.call(method, url, body)
+    }
+
+    /// GET `/_health`, sent once
+    fn health(&self) -> anyhow::Result<Call> {
+        let mut call = self.call(
+            reqwest::Method::GET,
+            format!("{}/_health", self.base_url),
+            None,
+        )?;
+        call.retries = 0;
+        Ok(call)
+    }
+}
+
+/// Helper: Whether an attempt is worth repeating: a `5xx` answer, a
+/// connection error or a timeout
+fn should_retry(outcome: Result<reqwest::StatusCode, &reqwest::Error>) -> bool {
+    match outcome {
+        Ok(status) => status.is_server_error(),
+        Err(e) => e.is_connect() || e.is_timeout() || e.is_request(),
+    }
+}
+
+/// Helper: The JSON body of a response; `null` for a `204` (e.g. a DELETE on
+/// a server using `with_delete_no_content`)
+fn json_body(status: reqwest::StatusCode, text: &str) -> anyhow::Result<Value> {
+    if status == reqwest::StatusCode::NO_CONTENT {
+        return Ok(Value::Null);
+    }
+    Ok(serde_json::from_str(text)?)
+}
+
+/// Helper: Whether a `/_health` answer means the server is up; an
+/// unreachable server is `Ok(false)`, not an error
+fn ping_result(outcome: Result<reqwest::StatusCode, reqwest::Error>) -> anyhow::Result<bool> {
+    match outcome {
+        Ok(status) => Ok(status == reqwest::StatusCode::OK),
+        Err(e) if e.is_connect() || e.is_timeout() || e.is_request() => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Helper: `{base}/{table}` plus the filter parameters as a query string
+fn list_url(base_url: &str, table: &str, params: Option<HashMap<&str, &str>>) -> String {
+    let mut url = format!("{}/{}", base_url, table);
+
+    // If there are filter parameters, add them to the URL
+    if let Some(p) = params {
+        let query_str: Vec<String> = p.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        if !query_str.is_empty() {
+            url.push_str(&format!("?{}", query_str.join("&")));
+        }
+    }
+    url
+}
+
+/// Helper: The keys of a `get_many` call as they appear in the URL
+fn key_strings(ids: &[impl std::fmt::Display]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+/// Helper: Puts the rows of a `{key}__in` listing back into the order of `ids`
+fn order_by_ids(res: Value, key: &str, ids: &[String]) -> anyhow::Result<Vec<Value>> {
+    let rows = match res {
+        Value::Array(rows) => rows,
+        other => return Err(anyhow::anyhow!("Unexpected response: {}", other)),
+    };
+
+    // The server returns rows in table order, so reorder them client-side.
+    // Keys are compared the way they were sent, so `1` matches `1` and `"a"` `a`.
+    let mut by_id: HashMap<String, Value> = rows
+        .into_iter()
+        .filter_map(|row| {
+            let id = match &row[key] {
+                Value::Null => return None,
+                Value::String(id) => id.clone(),
+                id => id.to_string(),
+            };
+            Some((id, row))
+        })
+        .collect();
+    Ok(ids.iter().filter_map(|id| by_id.remove(id)).collect())
+}
+
+/// Helper: Parses `<target>; rel="name", ...` into a map from rel to target
+fn parse_links(header: &str) -> HashMap<String, String> {
+    header
+        .split(',')
+        .filter_map(|link| {
+            let (target, params) = link.split_once(';')?;
+            let target = target.trim().strip_prefix('<')?.strip_suffix('>')?;
+            let rel = params
+                .split(';')
+                .find_map(|p| p.trim().strip_prefix("rel="))?
+                .trim_matches('"');
+            Some((rel.to_string(), target.to_string()))
+        })
+        .collect()
+}
+
+/// Deserializes a response body, surfacing the server's `{"error": ...}` message
+fn from_response<T: DeserializeOwned>(res: Value) -> anyhow::Result<T> {
+    if let Some(error) = res.get("error") {
+        return Err(anyhow::anyhow!("Server error: {}", error));
+    }
+    Ok(serde_json::from_value(res)?)
+}
+
+/// Synchronous counterpart of `EasyClient` for callers without an async runtime
+/// (feature `blocking`). Must not be used from inside a tokio runtime.
+#[cfg(feature = "blocking")]
+pub struct BlockingClient {
+    pub base_url: String,
+    settings: Settings,
+    client: reqwest::blocking::Client,
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingClient {
+    /// Creates a new client (e.g., localhost, 9000)
+    pub fn new(host: &str, port: u16) -> Self {
+        Self {
+            base_url: format!("http://{}:{}", host, port),
+            settings: Settings::default(),
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+
+    /// Sends the given API key with every request (`X-API-Key` header)
+    pub fn with_api_key(mut self, key: &str) -> Self {
+        self.settings.api_key = Some(key.to_string());
+        self
+    }
+
+    /// Talks to a server mounted under a prefix (see `EasyDB::with_prefix`)
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.base_url.push_str(prefix.trim_end_matches('/'));
+        self
+    }
+
+    /// Uses an existing `reqwest::blocking::Client`
+    pub fn with_client(mut self, client: reqwest::blocking::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Fails any request that takes longer than `timeout` (connecting included)
+    /// (replaces any client set with `with_client`)
+    pub fn with_timeout(mut self, timeout: Duration) -> anyhow::Result<Self> {
+        self.client = reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .build()?;
+        Ok(self)
+    }
+
+    /// Retries idempotent requests (GET, PUT, DELETE) up to `retries` times on
+    /// connection errors, timeouts and `5xx` responses, with exponential backoff
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.settings.retries = retries;
+        self
+    }
+
+    /// Helper: Builds calls from this client's base URL and settings
+    fn calls(&self) -> Calls<'_> {
+        Calls {
+            base_url: &self.base_url,
+            settings: &self.settings,
+        }
+    }
+
+    /// Sends a call and parses the JSON response body
+    fn send(&self, call: Call) -> anyhow::Result<Value> {
+        let res = self.send_raw(&call)?;
+        let status = res.status();
+        json_body(status, &res.text()?)
+    }
+
+    /// Sends a call, retrying it as often as it allows
+    fn send_raw(&self, call: &Call) -> Result<reqwest::blocking::Response, reqwest::Error> {
+        let mut delay = RETRY_BASE_DELAY;
+        for attempt in 0..=call.retries {
+            let mut req = self
+                .client
+                .request(call.method.clone(), &call.url)
+                .headers(call.headers.clone());
+            if let Some(body) = &call.body {
+                req = req.body(body.clone());
+            }
+            let res = req.send();
+            if attempt == call.retries || !should_retry(res.as_ref().map(|r| r.status())) {
+                return res;
+            }
+            std::thread::sleep(delay);
+            delay *= 2;
+        }
+        unreachable!("the last attempt always returns")
+    }
+
+    /// Sends a GET request (Supports Filtering and Sorting)
+    pub fn get(&self, table: &str, params: Option<HashMap<&str, &str>>) -> anyhow::Result<Value> {
+        self.send(self.calls().list(table, params)?)
+    }
+
+    /// Like `get`, but also returns the pagination headers
+    pub fn get_page(
+        &self,
+        table: &str,
+        params: Option<HashMap<&str, &str>>,
+    ) -> anyhow::Result<Page> {
+        let res = self.send_raw(&self.calls().list(table, params)?)?;
+        let (status, headers) = (res.status(), res.headers().clone());
+        Page::from_parts(&headers, json_body(status, &res.text()?)?)
+    }
+
+    /// Sends a GET request built with `Query` (filters, sorting, pagination)
+    pub fn query(&self, table: &str, query: &Query) -> anyhow::Result<Value> {
+        self.send(self.calls().query(table, query)?)
+    }
+
+    /// Like `query`, but deserializes the rows into `T`
+    pub fn query_as<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        query: &Query,
+    ) -> anyhow::Result<Vec<T>> {
+        from_response(self.query(table, query)?)
+    }
+
+    /// Like `get`, but deserializes the rows into `T`
+    pub fn get_as<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        params: Option<HashMap<&str, &str>>,
+    ) -> anyhow::Result<Vec<T>> {
+        from_response(self.get(table, params)?)
+    }
+
+    /// Fetches a single record by id (Supports `_expand` / `_embed`)
+    pub fn get_by_id(
+        &self,
+        table: &str,
+        id: impl std::fmt::Display,
+        params: Option<HashMap<&str, &str>>,
+    ) -> anyhow::Result<Value> {
+        self.get(&format!("{}/{}", table, id), params)
+    }
+
+    /// Like `get_by_id`, but deserializes the record into `T`
+    pub fn get_by_id_as<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        id: impl std::fmt::Display,
+        params: Option<HashMap<&str, &str>>,
+    ) -> anyhow::Result<T> {
+        from_response(self.get_by_id(table, id, params)?)
+    }
+
+    /// Fetches the records with the given ids, in the order the ids were given
+    pub fn get_many(&self, table: &str, ids: &[i64]) -> anyhow::Result<Vec<Value>> {
+        self.get_many_by(table, "id", ids)
+    }
+
+    /// Like `get_many`, for a table addressed by another key column
+    pub fn get_many_by(
+        &self,
+        table: &str,
+        key: &str,
+        ids: &[impl std::fmt::Display],
+    ) -> anyhow::Result<Vec<Value>> {
+        let ids = key_strings(ids);
+        order_by_ids(self.send(self.calls().many(table, key, &ids)?)?, key, &ids)
+    }
+
+    /// Sends a POST request (Create Data)
+    pub fn post<T: Serialize>(&self, table: &str, data: T) -> anyhow::Result<Value> {
+        self.send(self.calls().post(table, data)?)
+    }
+
+    /// Sends an UPSERT request (Create or Update Data by a unique column)
+    pub fn upsert<T: Serialize>(
+        &self,
+        table: &str,
+        conflict: &str,
+        data: T,
+    ) -> anyhow::Result<Value> {
+        self.send(self.calls().upsert(table, conflict, data)?)
+    }
+
+    /// Sends a PUT request (Update Data)
+    pub fn put<T: Serialize>(
+        &self,
+        table: &str,
+        id: impl std::fmt::Display,
+        data: T,
+    ) -> anyhow::Result<Value> {
+        self.send(self.calls().put(table, id, data)?)
+    }
+
+    /// Sends a PATCH request with JSON merge-patch semantics: `null` clears a column
+    pub fn patch<T: Serialize>(
+        &self,
+        table: &str,
+        id: impl std::fmt::Display,
+        data: T,
+    ) -> anyhow::Result<Value> {
+        self.send(self.calls().patch(table, id, data)?)
+    }
+
+    /// Sends a DELETE request (Delete Data)
+    pub fn delete(&self, table: &str, id: impl std::fmt::Display) -> anyhow::Result<Value> {
+        self.send(self.calls().delete(table, id)?)
+    }
+
+    /// Sends any request to `path` and returns the status with the body
+    /// (see `EasyClient::request`)
+    pub fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> anyhow::Result<ApiResponse> {
+        let res = self.send_raw(&self.calls().request(method, path, body)?)?;
+        let status = res.status();
+        Ok(ApiResponse::from_parts(status, &res.text()?))
+    }
+
+    /// Whether the server answers `GET /_health` with `200` (see `EasyClient::ping`)
+    pub fn ping(&self) -> anyhow::Result<bool> {
+        let res = self.send_raw(&self.calls().health()?);
+        ping_result(res.map(|r| r.status()))
+    }
+
+    /// Pings the server until it is up (see `EasyClient::wait_until_ready`)
+    pub fn wait_until_ready(&self, timeout: Duration) -> anyhow::Result<()> {
+        let deadline = std::time::Instant::now() + timeout;
+        while !self.ping()? {
+            if std::time::Instant::now() >= deadline {
+                anyhow::bail!("Server at {} not ready after {:?}", self.base_url, timeout);
+            }
+            std::thread::sleep(READY_POLL_INTERVAL);
+        }
+        Ok(())
+    }
+}
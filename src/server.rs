@@ -0,0 +1,6046 @@
+//! The server: `EasyDB` exposes SQLite tables as a REST API (feature `server`).
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{any, delete, get, patch, post, put, MethodRouter},
+    Json, Router,
+};
+use rusqlite::{types::ValueRef, ToSql};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::{broadcast, mpsc, watch};
+pub use tower_http::cors::CorsLayer;
+use tower_http::trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer};
+
+pub use rusqlite::{Connection, OpenFlags};
+// The axum version `with_route` expects, for building your own routes
+pub use axum;
+
+// --- SECURITY CHECK ---
+// SQL Injection protection: Ensures table and column names only contain safe characters.
+fn is_valid_identifier(name: &str) -> bool {
+    name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// Identifiers are quoted in generated SQL so keywords like `order` work as names.
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn quoted_list(names: &[impl AsRef<str>]) -> String {
+    let quoted: Vec<String> = names.iter().map(|n| quote_ident(n.as_ref())).collect();
+    quoted.join(", ")
+}
+
+// Route prefixes: `/`-separated segments of letters, digits, `_` and `-`.
+fn is_valid_prefix(prefix: &str) -> bool {
+    prefix.starts_with('/')
+        && !prefix.ends_with('/')
+        && !prefix.contains("//")
+        && prefix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '/'))
+}
+
+// Opt-in strict naming: lowercase snake_case, no leading digit, no consecutive underscores.
+fn is_snake_case_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && !name.contains("__")
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+// =========================================================
+// 1. SERVER PART (EasyDB)
+// =========================================================
+
+/// A server whose listener is bound but not yet serving (see `EasyDB::bind`)
+pub struct BoundServer {
+    local_addr: SocketAddr,
+    listener: tokio::net::TcpListener,
+    app: Router,
+    queues: WriteQueues,
+    drain_timeout: Option<Duration>,
+}
+
+/// The background tasks that write `batch_writes` queues (see
+/// `EasyDB::into_router`)
+pub struct WriteQueues {
+    flushers: Vec<tokio::task::JoinHandle<()>>,
+}
+
+impl WriteQueues {
+    /// Waits until every queued row has been written. The queues close once
+    /// the router and all of its clones are dropped, so drop them first or
+    /// this never completes.
+    pub async fn flush(self) {
+        for flusher in self.flushers {
+            let _ = flusher.await;
+        }
+    }
+}
+
+impl BoundServer {
+    /// The address the listener is bound to, with the actual port
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Serves requests until the process ends
+    pub async fn serve(self) -> anyhow::Result<()> {
+        self.serve_with_shutdown(std::future::pending()).await
+    }
+
+    /// Serves requests and shuts down gracefully once `signal` completes
+    /// (see `EasyDB::run_server_with_shutdown`)
+    pub async fn serve_with_shutdown<F>(self, signal: F) -> anyhow::Result<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        println!("🚀 Easy-DB Server is running: http://{}", self.local_addr);
+
+        // Flipped to `true` when the drain deadline passes
+        let (close_tx, close_rx) = watch::channel(false);
+        let (signal_tx, signal_rx) = tokio::sync::oneshot::channel::<()>();
+        let listener = DrainListener {
+            inner: self.listener,
+            closed: close_rx,
+        };
+
+        let app = self.app.into_make_service_with_connect_info::<ClientAddr>();
+        let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+            signal.await;
+            let _ = signal_tx.send(());
+        });
+
+        let drain_timeout = self.drain_timeout;
+        let deadline = async move {
+            match (signal_rx.await, drain_timeout) {
+                (Ok(()), Some(timeout)) => tokio::time::sleep(timeout).await,
+                _ => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            res = std::future::IntoFuture::into_future(server) => res?,
+            _ = deadline => {
+                println!("⏱️ Drain timeout reached, closing remaining connections.");
+                let _ = close_tx.send(true);
+            }
+        }
+
+        // The queues close once the last request holding them is gone
+        self.queues.flush().await;
+        Ok(())
+    }
+}
+
+/// An accepted API key and the scopes it grants
+#[derive(Clone)]
+struct ApiKey {
+    key: String,
+    scopes: HashSet<String>,
+}
+
+/// Scopes of the API key that authenticated the request (a request extension)
+#[derive(Clone, Default)]
+struct Scopes(HashSet<String>);
+
+impl<S: Send + Sync> axum::extract::FromRequestParts<S> for Scopes {
+    type Rejection = std::convert::Infallible;
+
+    /// Requests without an API key (or with an unscoped one) have no scopes
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<Scopes>()
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+/// Checks a written value; `Err` holds the message shown to the client
+type Validator = Arc<dyn Fn(&Value) -> Result<(), String> + Send + Sync>;
+
+/// Replaces a column's value for callers without the unmasking scope
+#[derive(Clone)]
+struct ColumnMask {
+    scope: String,
+    mask: Arc<dyn Fn(&Value) -> Value + Send + Sync>,
+}
+
+/// Route of the built-in health check (never an exposed table)
+const HEALTH_PATH: &str = "/_health";
+
+/// Route of the transactional batch endpoint (see `handle_batch`)
+const BATCH_PATH: &str = "/_batch";
+
+/// Route of the backup endpoint (see `with_backup_endpoint`)
+const BACKUP_PATH: &str = "/_backup";
+
+/// Copies the main database to the given file (see `EasyDB::backup_to`)
+type BackupFn = Arc<dyn Fn(&std::path::Path) -> anyhow::Result<()> + Send + Sync>;
+
+/// Largest request body accepted unless `with_max_body_size` says otherwise
+const DEFAULT_MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// Main library structure (Server Engine)
+pub struct EasyDB {
+    pub db_name: String,
+    conn: Arc<Mutex<Connection>>,
+    exposed_tables: Vec<String>,
+    source: DbSource,
+    table_dbs: HashMap<String, MappedDb>,
+    on_connect: Option<ConnectHook>,
+    table_configs: HashMap<String, TableConfig>,
+    drain_timeout: Option<Duration>,
+    read_only: bool,
+    config: DbConfig,
+    relations: Vec<Relation>,
+    logging: bool,
+    compression: bool,
+    cors: CorsLayer,
+    strict_identifiers: bool,
+    strict_column_types: bool,
+    strict_hidden_columns: bool,
+    no_content_deletes: bool,
+    csv_null: String,
+    busy_retry: BusyRetry,
+    scan_guard: Option<u64>,
+    max_body_size: usize,
+    rate_limit: Option<(u32, Duration)>,
+    introspection: bool,
+    prefix: String,
+    api_keys: Vec<ApiKey>,
+    clock: Arc<dyn Clock>,
+    /// Other databases served under their own prefixes (see `mount`)
+    mounts: Vec<(String, EasyDB)>,
+    /// Directory `POST /_backup` writes snapshots to
+    backup_dir: Option<PathBuf>,
+    /// Application routes served next to the generated ones (see `with_route`)
+    extra_routes: Vec<(String, MethodRouter)>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<Arc<Metrics>>,
+}
+
+/// Where the main connection was opened from, kept so `reopen` can open it again.
+enum DbSource {
+    File { path: PathBuf, flags: OpenFlags },
+    Memory,
+}
+
+/// A table stored in its own database file (see `map_table_to_db`).
+struct MappedDb {
+    path: String,
+    conn: Arc<Mutex<Connection>>,
+}
+
+/// Callback run on every freshly opened connection.
+type ConnectHook = Arc<dyn Fn(&Connection) -> rusqlite::Result<()> + Send + Sync>;
+
+/// A declared relationship: `child.column` references `parent.column`
+#[derive(Clone)]
+struct Relation {
+    parent: String,
+    child: String,
+    column: String,
+    /// Delete the child rows along with their parent
+    cascade: bool,
+}
+
+/// Source of the current time for server-written timestamps.
+/// Tests can swap in a fixed clock with `EasyDB::with_clock`.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// The default clock: the operating system's wall clock
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Connection settings applied right after a database file is opened
+#[derive(Debug, Clone)]
+pub struct DbConfig {
+    /// Use write-ahead logging so readers and a writer don't block each other
+    pub wal: bool,
+    /// How long to wait on a locked database before failing, in milliseconds
+    pub busy_timeout_ms: u64,
+    /// Enforce `REFERENCES` constraints (SQLite leaves them off by default)
+    pub foreign_keys: bool,
+    /// Overwrite deleted content with zeros (`PRAGMA secure_delete`).
+    /// Costs extra disk writes on every DELETE and UPDATE, so writes get slower
+    /// and the WAL/journal grows larger.
+    pub secure_delete: bool,
+}
+
+impl Default for DbConfig {
+    fn default() -> Self {
+        Self {
+            wal: false,
+            busy_timeout_ms: 5000,
+            foreign_keys: true,
+            secure_delete: false,
+        }
+    }
+}
+
+/// How a column's stored value is converted to and from JSON
+/// (see `EasyDB::column_kind`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    /// TEXT holding a JSON document, returned as nested JSON instead of a string
+    Json,
+    /// INTEGER 0/1 returned as `false`/`true`; JSON booleans are stored as 0/1
+    Bool,
+    /// TEXT timestamp: writes must be RFC3339 and are stored in UTC
+    /// (`2024-01-01T09:30:00.000Z`), so comparisons order chronologically
+    DateTime,
+}
+
+/// Per-table settings applied by the handlers
+#[derive(Clone, Default)]
+struct TableConfig {
+    /// Columns that are never returned in responses
+    hidden_columns: HashSet<String>,
+    /// Columns whose values are hashed before being written
+    hashed_columns: HashSet<String>,
+    /// Columns shown masked unless the request has the rule's scope
+    masks: HashMap<String, ColumnMask>,
+    /// Column addressed by `/{table}/{id}` instead of `id`
+    primary_key: Option<String>,
+    /// Columns converted to and from JSON by kind (see `column_kind`)
+    kinds: HashMap<String, ColumnKind>,
+    /// App-level checks run on written values, in registration order
+    validators: HashMap<String, Vec<Validator>>,
+    /// Human-readable column descriptions shown by the schema endpoint
+    descriptions: HashMap<String, String>,
+    /// Whether `created_at` / `updated_at` are filled in by the server
+    timestamps: bool,
+    /// Whether `GET /{table}/search` is served from a `{table}_fts` index
+    full_text: bool,
+    /// Whether DELETE only sets `deleted_at` (see `soft_delete`)
+    soft_delete: bool,
+    /// Queue POSTs and insert them in batches (see `batch_writes`)
+    batch: Option<BatchConfig>,
+    /// Path segment of the table's routes, if not its name (see `route_alias`)
+    route: Option<String>,
+    /// Column (and whether descending) listings sort by without `_sort`
+    default_sort: Option<(String, bool)>,
+}
+
+/// When a table's write queue is flushed
+#[derive(Clone, Copy)]
+struct BatchConfig {
+    size: usize,
+    interval: Duration,
+}
+
+/// Everything a handler needs to serve one table
+struct TableState {
+    name: String,
+    conn: Arc<Mutex<Connection>>,
+    config: TableConfig,
+    clock: Arc<dyn Clock>,
+    /// Reject filters and sorts that name a hidden column
+    strict_hidden_columns: bool,
+    /// Answer a successful `DELETE /{table}/{id}` with an empty `204`
+    no_content_deletes: bool,
+    /// How NULL is written in CSV exports (and recognized in imports)
+    csv_null: String,
+    /// How often a write is retried while the database is locked
+    busy_retry: BusyRetry,
+    /// Row count above which full-table-scan listings are rejected
+    scan_guard: Option<u64>,
+    /// The table's real columns, read from the schema at startup
+    columns: HashSet<String>,
+    /// NOT NULL columns without a default, which a POST must fill
+    required: HashSet<String>,
+    /// Columns that alone are the PRIMARY KEY or UNIQUE (upsert conflict targets)
+    unique: HashSet<String>,
+    /// TEXT-affinity columns matched by `_q`, minus hidden, hashed and masked ones
+    text_columns: Vec<String>,
+    /// Column matched by the `{id}` path segment (`id` unless configured)
+    primary_key: String,
+    /// Whether the key column has INTEGER affinity (ids are parsed as numbers)
+    integer_key: bool,
+    /// Row changes of every table, fed by the SQLite update hook
+    changes: broadcast::Sender<ChangeEvent>,
+    /// Write queue for tables using `batch_writes`
+    queue: Option<mpsc::Sender<Map<String, Value>>>,
+    /// Parent tables reachable with `_expand`
+    parents: Vec<RelatedTable>,
+    /// Child tables reachable with `_embed`
+    children: Vec<RelatedTable>,
+    /// Child tables whose matching rows are deleted with a row of this one
+    cascades: Vec<RelatedTable>,
+}
+
+/// Retry policy for writes that fail with `SQLITE_BUSY`
+#[derive(Clone, Copy, Default)]
+struct BusyRetry {
+    retries: u32,
+    /// Delay before the first retry; doubled after every attempt
+    backoff: Duration,
+}
+
+/// A row that was inserted, updated or deleted (sent to `/{table}/subscribe`)
+#[derive(Clone)]
+struct ChangeEvent {
+    table: String,
+    op: &'static str,
+    id: i64,
+}
+
+/// A table on the other side of a relation, queried by the same column
+struct RelatedTable {
+    name: String,
+    column: String,
+    conn: Arc<Mutex<Connection>>,
+    config: TableConfig,
+}
+
+/// Chainable configuration for an `EasyDB`, finished with `build()`:
+///
+/// `EasyDB::builder("app").wal(true).api_key("secret").prefix("/api").build()?`
+///
+/// Every option can also be set later with the matching `EasyDB::with_*`
+/// method; the builder just keeps them in one place.
+pub struct EasyDBBuilder {
+    name: String,
+    location: Location,
+    config: DbConfig,
+    flags: OpenFlags,
+    api_keys: Vec<String>,
+    prefix: Option<String>,
+    cors: Option<CorsLayer>,
+    max_body_size: Option<usize>,
+    logging: bool,
+    compression: bool,
+}
+
+/// Where an `EasyDBBuilder` opens its database
+enum Location {
+    /// `{name}.db` in the working directory
+    Named,
+    /// Exactly this file (see `EasyDB::init_at`)
+    Path(PathBuf),
+    Memory,
+}
+
+impl EasyDBBuilder {
+    /// Starts a configuration for the database `{name}.db`
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            location: Location::Named,
+            config: DbConfig::default(),
+            flags: OpenFlags::default(),
+            api_keys: Vec::new(),
+            prefix: None,
+            cors: None,
+            max_body_size: None,
+            logging: false,
+            compression: false,
+        }
+    }
+
+    /// Opens the file at exactly `path` instead, creating parent directories
+    pub fn path(mut self, path: impl AsRef<std::path::Path>) -> Self {
+        self.location = Location::Path(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Keeps the database in memory instead (nothing is written to disk)
+    pub fn in_memory(mut self) -> Self {
+        self.location = Location::Memory;
+        self
+    }
+
+    /// Replaces all connection settings at once
+    pub fn config(mut self, config: DbConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// See `DbConfig::wal`
+    pub fn wal(mut self, enabled: bool) -> Self {
+        self.config.wal = enabled;
+        self
+    }
+
+    /// See `DbConfig::busy_timeout_ms`
+    pub fn busy_timeout_ms(mut self, ms: u64) -> Self {
+        self.config.busy_timeout_ms = ms;
+        self
+    }
+
+    /// See `DbConfig::foreign_keys`
+    pub fn foreign_keys(mut self, enabled: bool) -> Self {
+        self.config.foreign_keys = enabled;
+        self
+    }
+
+    /// See `DbConfig::secure_delete`
+    pub fn secure_delete(mut self, enabled: bool) -> Self {
+        self.config.secure_delete = enabled;
+        self
+    }
+
+    /// See `EasyDB::init_with_flags`
+    pub fn flags(mut self, flags: OpenFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// See `EasyDB::with_api_key`
+    pub fn api_key(mut self, key: &str) -> Self {
+        self.api_keys.push(key.to_string());
+        self
+    }
+
+    /// See `EasyDB::with_prefix` (checked by `build`)
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// See `EasyDB::with_cors`
+    pub fn cors(mut self, cors: CorsLayer) -> Self {
+        self.cors = Some(cors);
+        self
+    }
+
+    /// See `EasyDB::with_max_body_size`
+    pub fn max_body_size(mut self, bytes: usize) -> Self {
+        self.max_body_size = Some(bytes);
+        self
+    }
+
+    /// See `EasyDB::with_logging`
+    pub fn logging(mut self, enabled: bool) -> Self {
+        self.logging = enabled;
+        self
+    }
+
+    /// See `EasyDB::with_compression`
+    pub fn compression(mut self, enabled: bool) -> Self {
+        self.compression = enabled;
+        self
+    }
+
+    /// Opens the database and applies every option
+    pub fn build(self) -> anyhow::Result<EasyDB> {
+        let mut db = match self.location {
+            Location::Named => {
+                let path = PathBuf::from(format!("{}.db", self.name));
+                EasyDB::open_file(&self.name, path, self.config, self.flags)?
+            }
+            Location::Path(path) => {
+                let name = prepare_db_path(&path)?;
+                EasyDB::open_file(&name, path, self.config, self.flags)?
+            }
+            Location::Memory => {
+                let conn = Connection::open_in_memory()?;
+                apply_config(&conn, &self.config)?;
+                let mut db = EasyDB::from_connection(&self.name, conn, DbSource::Memory);
+                db.config = self.config;
+                db
+            }
+        };
+
+        for key in &self.api_keys {
+            db = db.with_api_key(key);
+        }
+        if let Some(prefix) = &self.prefix {
+            db = db.with_prefix(prefix)?;
+        }
+        if let Some(cors) = self.cors {
+            db = db.with_cors(cors);
+        }
+        if let Some(bytes) = self.max_body_size {
+            db = db.with_max_body_size(bytes);
+        }
+        Ok(db
+            .with_logging(self.logging)
+            .with_compression(self.compression))
+    }
+}
+
+/// Helper: Creates the parent directories of a database file and returns the
+/// file name without its extension (used as `db_name`)
+fn prepare_db_path(path: &std::path::Path) -> anyhow::Result<String> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .ok_or_else(|| anyhow::anyhow!("Not a file path: {}", path.display()))
+}
+
+impl EasyDB {
+    /// Starts an `EasyDBBuilder` for the database `{name}.db`
+    pub fn builder(name: &str) -> EasyDBBuilder {
+        EasyDBBuilder::new(name)
+    }
+
+    /// Initializes the database connection.
+    pub fn init(name: &str) -> anyhow::Result<Self> {
+        Self::builder(name).build()
+    }
+
+    /// Initializes the database and applies the given connection settings
+    /// (journal mode, busy timeout) before any table is created.
+    pub fn init_with_config(name: &str, config: DbConfig) -> anyhow::Result<Self> {
+        Self::builder(name).config(config).build()
+    }
+
+    /// Opens (or creates) the database file at exactly `path`, e.g.
+    /// `/var/lib/myapp/data.db`, creating missing parent directories.
+    /// `db_name` is the file name without its extension.
+    pub fn init_at(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
+        Self::builder("").path(path).build()
+    }
+
+    /// Initializes the database with custom SQLite open flags.
+    ///
+    /// With `OpenFlags::SQLITE_OPEN_READ_ONLY` only the read routes are
+    /// generated and write requests are answered with `405 Method Not Allowed`.
+    pub fn init_with_flags(name: &str, flags: OpenFlags) -> anyhow::Result<Self> {
+        Self::builder(name).flags(flags).build()
+    }
+
+    /// Initializes an in-memory database (nothing is written to disk).
+    ///
+    /// Note: every SQLite in-memory database belongs to the connection that
+    /// opened it. A pooled setup would need a shared-cache URI such as
+    /// `file:{name}?mode=memory&cache=shared` for all connections to see the same data.
+    pub fn init_in_memory(name: &str) -> anyhow::Result<Self> {
+        Self::builder(name).in_memory().build()
+    }
+
+    /// Opens a database file with the given settings and flags
+    fn open_file(
+        name: &str,
+        path: PathBuf,
+        config: DbConfig,
+        flags: OpenFlags,
+    ) -> anyhow::Result<Self> {
+        let conn = Connection::open_with_flags(&path, flags)?;
+        apply_config(&conn, &config)?;
+
+        let source = DbSource::File { path, flags };
+        let mut db = Self::from_connection(name, conn, source);
+        db.config = config;
+        db.read_only = flags.contains(OpenFlags::SQLITE_OPEN_READ_ONLY);
+        Ok(db)
+    }
+
+    fn from_connection(name: &str, conn: Connection, source: DbSource) -> Self {
+        Self {
+            db_name: name.to_string(),
+            conn: Arc::new(Mutex::new(conn)),
+            exposed_tables: Vec::new(),
+            source,
+            table_dbs: HashMap::new(),
+            on_connect: None,
+            table_configs: HashMap::new(),
+            drain_timeout: None,
+            read_only: false,
+            config: DbConfig::default(),
+            relations: Vec::new(),
+            logging: false,
+            compression: false,
+            cors: CorsLayer::permissive(),
+            strict_identifiers: false,
+            strict_column_types: false,
+            strict_hidden_columns: false,
+            no_content_deletes: false,
+            csv_null: String::new(),
+            busy_retry: BusyRetry::default(),
+            scan_guard: None,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            rate_limit: None,
+            introspection: true,
+            prefix: String::new(),
+            api_keys: Vec::new(),
+            clock: Arc::new(SystemClock),
+            mounts: Vec::new(),
+            backup_dir: None,
+            extra_routes: Vec::new(),
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+
+    /// Logs every request (method, path, status, elapsed time) through the
+    /// `tracing` crate. Install a subscriber (e.g. `tracing-subscriber`) to see them.
+    pub fn with_logging(mut self, enabled: bool) -> Self {
+        self.logging = enabled;
+        self
+    }
+
+    /// Replaces the default CORS policy, which allows every origin, method
+    /// and header (`CorsLayer::permissive()`).
+    pub fn with_cors(mut self, cors: CorsLayer) -> Self {
+        self.cors = cors;
+        self
+    }
+
+    /// Compresses responses (JSON and CSV alike) with gzip or deflate for
+    /// clients that send a matching `Accept-Encoding` header.
+    pub fn with_compression(mut self, enabled: bool) -> Self {
+        self.compression = enabled;
+        self
+    }
+
+    /// Requires every request to carry this key, either as an `X-API-Key`
+    /// header or as `Authorization: Bearer <key>`. May be called several
+    /// times to accept more than one key.
+    pub fn with_api_key(self, key: &str) -> Self {
+        self.with_scoped_api_key(key, &[])
+    }
+
+    /// Like `with_api_key`, but requests made with this key carry the given
+    /// scopes (see `mask_column`).
+    pub fn with_scoped_api_key(mut self, key: &str, scopes: &[&str]) -> Self {
+        self.api_keys.push(ApiKey {
+            key: key.to_string(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+        });
+        self
+    }
+
+    /// Rejects table and column names that aren't snake_case in `create_table`.
+    pub fn with_strict_identifiers(mut self, enabled: bool) -> Self {
+        self.strict_identifiers = enabled;
+        self
+    }
+
+    /// Rejects column types in `create_table` that aren't in the list of
+    /// common type names (e.g. `age INTEGR`), instead of only warning.
+    pub fn with_strict_column_types(mut self, enabled: bool) -> Self {
+        self.strict_column_types = enabled;
+        self
+    }
+
+    /// Answers filters and sorts on hidden columns with `400`, as if the column
+    /// didn't exist. Otherwise clients could infer hidden values from which
+    /// rows a filter returns.
+    pub fn with_strict_hidden_columns(mut self, enabled: bool) -> Self {
+        self.strict_hidden_columns = enabled;
+        self
+    }
+
+    /// Answers a successful `DELETE /{table}/{id}` with `204 No Content` and
+    /// no body instead of `200` with a JSON body, for REST tooling that
+    /// expects it. A missing record is still a `404`.
+    pub fn with_delete_no_content(mut self, enabled: bool) -> Self {
+        self.no_content_deletes = enabled;
+        self
+    }
+
+    /// Sets how NULL values are written in CSV exports, e.g. `NULL` or `\N`
+    /// (default: an empty field). CSV imports treat fields equal to it as NULL.
+    pub fn with_csv_null(mut self, token: &str) -> Self {
+        self.csv_null = token.to_string();
+        self
+    }
+
+    /// Rejects listings and CSV exports with `400` when SQLite's query plan
+    /// (`EXPLAIN QUERY PLAN`) would scan the whole table and the table holds
+    /// more than `max_rows` rows. Filter on an indexed column to get through.
+    pub fn with_scan_guard(mut self, max_rows: u64) -> Self {
+        self.scan_guard = Some(max_rows);
+        self
+    }
+
+    /// Caps request bodies (JSON and CSV) at `bytes`; larger ones are refused
+    /// with `413 Payload Too Large` before being read. Defaults to 2 MiB.
+    pub fn with_max_body_size(mut self, bytes: usize) -> Self {
+        self.max_body_size = bytes;
+        self
+    }
+
+    /// Serves `/{table}/schema` and `/{table}/ddl` and lists the exposed
+    /// tables in 404s for unknown ones (the default). Turn it off in
+    /// locked-down deployments so clients can't discover the schema.
+    pub fn with_introspection(mut self, enabled: bool) -> Self {
+        self.introspection = enabled;
+        self
+    }
+
+    /// Allows each client IP `requests` requests per `per`, refilled evenly
+    /// (a token bucket). Excess requests get `429 Too Many Requests` with a
+    /// `Retry-After` header. Requests rejected by API-key auth don't count.
+    pub fn with_rate_limit(mut self, requests: u32, per: Duration) -> Self {
+        self.rate_limit = Some((requests.max(1), per));
+        self
+    }
+
+    /// Serves `GET /metrics` in the Prometheus text format: request counts
+    /// by table, method and status (`easydb_requests_total`) and a latency
+    /// histogram by table and method (`easydb_request_duration_seconds`).
+    /// The endpoint needs an API key like any other route.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self) -> Self {
+        self.metrics = Some(Arc::new(Metrics::default()));
+        self
+    }
+
+    /// Retries writes that fail because the database is locked (`SQLITE_BUSY`)
+    /// up to `retries` times, waiting `backoff` before the first retry and
+    /// doubling it each time. A write that is still locked out is answered
+    /// with `503 Service Unavailable`.
+    ///
+    /// This comes on top of `DbConfig::busy_timeout_ms`, which SQLite itself
+    /// waits before reporting the database as busy.
+    pub fn with_busy_retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.busy_retry = BusyRetry { retries, backoff };
+        self
+    }
+
+    /// Mounts every route under a prefix, e.g. `/api/v1` serves
+    /// `/api/v1/students` and `/api/v1/_health`. The prefix must start with
+    /// `/`, must not end with one, and may only contain letters, digits, `_`,
+    /// `-` and `/`.
+    pub fn with_prefix(mut self, prefix: &str) -> anyhow::Result<Self> {
+        if !is_valid_prefix(prefix) {
+            return Err(anyhow::anyhow!("Invalid route prefix: {}", prefix));
+        }
+        self.prefix = prefix.to_string();
+        Ok(self)
+    }
+
+    /// Serves your own route (a login handler, a computed report, ...) at
+    /// `path`, behind the same middleware as the generated ones: prefix, API
+    /// keys, rate limiting, CORS and tracing. Calls add up; give all methods
+    /// of one path in a single call (`get(a).post(b)`). A path whose first
+    /// segment is already routed (a table, `_health`, a mount, ...) or that
+    /// was given before makes `bind` / `into_router` fail.
+    pub fn with_route(mut self, path: &str, route: MethodRouter) -> Self {
+        self.extra_routes.push((path.to_string(), route));
+        self
+    }
+
+    /// Serves another database under `prefix` from the same server, e.g.
+    /// mounting `library_db` at `/library` serves `/library/books` next to
+    /// this database's own tables. The prefix follows the `with_prefix`
+    /// rules. The mounted database keeps its own settings (API keys, CORS,
+    /// read-only, ...), which apply under its prefix on top of this one's.
+    pub fn mount(mut self, prefix: &str, db: EasyDB) -> anyhow::Result<Self> {
+        if !is_valid_prefix(prefix) {
+            return Err(anyhow::anyhow!("Invalid route prefix: {}", prefix));
+        }
+        self.mounts.push((prefix.to_string(), db));
+        Ok(self)
+    }
+
+    /// Replaces the clock used for `auto_timestamps` (e.g. a fixed clock in tests).
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Limits how long in-flight requests may keep running after a shutdown
+    /// signal. Connections still open when the deadline passes are closed.
+    pub fn with_drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = Some(timeout);
+        self
+    }
+
+    /// Stores a table in a separate database file instead of the main one.
+    /// Call this before `create_table` so the table is created in that file.
+    /// The file is opened with the main database's flags, so a read-only
+    /// database maps tables read-only too.
+    pub fn map_table_to_db(&mut self, table_name: &str, path: &str) -> anyhow::Result<()> {
+        if !is_valid_identifier(table_name) {
+            return Err(anyhow::anyhow!("Invalid table name: {}", table_name));
+        }
+
+        let conn = Connection::open_with_flags(path, self.flags())?;
+        self.prepare_connection(&conn)?;
+        self.table_dbs.insert(
+            table_name.to_string(),
+            MappedDb {
+                path: path.to_string(),
+                conn: Arc::new(Mutex::new(conn)),
+            },
+        );
+        println!("🔀 Table '{}' mapped to '{}'.", table_name, path);
+        Ok(())
+    }
+
+    /// Helper: The flags the main database file was opened with (the default
+    /// ones for an in-memory database), also used for mapped files
+    fn flags(&self) -> OpenFlags {
+        match &self.source {
+            DbSource::File { flags, .. } => *flags,
+            DbSource::Memory => OpenFlags::default(),
+        }
+    }
+
+    /// Returns the connection that owns the given table.
+    fn conn_for(&self, table_name: &str) -> Arc<Mutex<Connection>> {
+        let conn = self
+            .table_dbs
+            .get(table_name)
+            .map(|mapped| &mapped.conn)
+            .unwrap_or(&self.conn);
+        Arc::clone(conn)
+    }
+
+    /// Runs a callback on every connection, now and whenever one is opened
+    /// again by `map_table_to_db` or `reopen` (e.g. to register SQL functions).
+    pub fn on_connect<F>(&mut self, hook: F) -> anyhow::Result<()>
+    where
+        F: Fn(&Connection) -> rusqlite::Result<()> + Send + Sync + 'static,
+    {
+        hook(&lock_conn(&self.conn))?;
+        for mapped in self.table_dbs.values() {
+            hook(&lock_conn(&mapped.conn))?;
+        }
+        self.on_connect = Some(Arc::new(hook));
+        Ok(())
+    }
+
+    /// Closes and reopens every connection to the same path, re-running the
+    /// connection settings and the `on_connect` hook. Use it after the
+    /// database file was replaced (e.g. restored from a backup).
+    ///
+    /// Call it before `run_server`, which takes ownership of the database.
+    pub fn reopen(&mut self) -> anyhow::Result<()> {
+        let DbSource::File { path, flags } = &self.source else {
+            return Err(anyhow::anyhow!("An in-memory database cannot be reopened"));
+        };
+
+        let conn = Connection::open_with_flags(path, *flags)?;
+        self.prepare_connection(&conn)?;
+        *lock_conn(&self.conn) = conn;
+
+        for mapped in self.table_dbs.values() {
+            let conn = Connection::open_with_flags(&mapped.path, *flags)?;
+            self.prepare_connection(&conn)?;
+            *lock_conn(&mapped.conn) = conn;
+        }
+        println!("🔄 Database '{}' reopened.", self.db_name);
+        Ok(())
+    }
+
+    /// Runs `VACUUM` on every database file, rebuilding it so the space of
+    /// deleted rows is given back to the file system. Needs free disk space
+    /// of about the file's size and blocks other writers while it runs.
+    pub fn vacuum(&self) -> anyhow::Result<()> {
+        self.each_connection(|conn| conn.execute_batch("VACUUM"))
+    }
+
+    /// Runs `ANALYZE` on every database file to refresh the statistics the
+    /// query planner uses to pick indexes.
+    pub fn analyze(&self) -> anyhow::Result<()> {
+        self.each_connection(|conn| conn.execute_batch("ANALYZE"))
+    }
+
+    /// Runs `op` on the main connection and on every mapped table's
+    fn each_connection(
+        &self,
+        op: impl Fn(&Connection) -> rusqlite::Result<()>,
+    ) -> anyhow::Result<()> {
+        op(&lock_conn(&self.conn))?;
+        for mapped in self.table_dbs.values() {
+            op(&lock_conn(&mapped.conn))?;
+        }
+        Ok(())
+    }
+
+    /// Copies the main database to `path` with SQLite's online backup API,
+    /// e.g. for periodic snapshots. A file database is read through a
+    /// connection of its own, so requests keep being served; the copy is a
+    /// consistent snapshot that includes WAL-mode changes not yet
+    /// checkpointed. Tables moved by `map_table_to_db` are not included.
+    pub fn backup_to(&self, path: impl AsRef<std::path::Path>) -> anyhow::Result<()> {
+        (self.backup_fn())(path.as_ref())
+    }
+
+    /// Serves `POST /_backup`, which writes a snapshot of the main database
+    /// (see `backup_to`) to `dir` as `{db_name}-{UTC time}.db` and answers
+    /// with its path. It copies the whole database, so an API key is
+    /// required (`with_api_key`); starting the server without one fails.
+    pub fn with_backup_endpoint(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.backup_dir = Some(dir.into());
+        self
+    }
+
+    /// The backup routine, detached from `self` so a running server can call it
+    fn backup_fn(&self) -> BackupFn {
+        let conn = Arc::clone(&self.conn);
+        let file = match &self.source {
+            DbSource::File { path, flags } => Some((path.clone(), *flags)),
+            DbSource::Memory => None,
+        };
+        let config = self.config.clone();
+        let on_connect = self.on_connect.clone();
+        Arc::new(move |dest| match &file {
+            Some((path, flags)) => {
+                let source = Connection::open_with_flags(path, *flags)?;
+                apply_config(&source, &config)?;
+                if let Some(hook) = &on_connect {
+                    hook(&source)?;
+                }
+                copy_database(&source, dest)
+            }
+            // Only the server's own connection can see an in-memory database
+            None => copy_database(&lock_conn(&conn), dest),
+        })
+    }
+
+    /// Applies the connection settings and the `on_connect` hook to a new connection.
+    fn prepare_connection(&self, conn: &Connection) -> anyhow::Result<()> {
+        apply_config(conn, &self.config)?;
+        if let Some(hook) = &self.on_connect {
+            hook(conn)?;
+        }
+        Ok(())
+    }
+
+    /// Hides a column from every API response. The column can still be written.
+    pub fn hide_column(&mut self, table_name: &str, column: &str) -> anyhow::Result<()> {
+        self.table_config(table_name, column)?
+            .hidden_columns
+            .insert(column.to_string());
+        Ok(())
+    }
+
+    /// Hashes a column (e.g. a password) with Argon2 on every insert/update.
+    /// The column is hidden as well, so the hash is never returned.
+    #[cfg(feature = "hashing")]
+    pub fn hash_column(&mut self, table_name: &str, column: &str) -> anyhow::Result<()> {
+        let config = self.table_config(table_name, column)?;
+        config.hashed_columns.insert(column.to_string());
+        config.hidden_columns.insert(column.to_string());
+        Ok(())
+    }
+
+    /// Shows `mask(value)` instead of the column's value to every request
+    /// whose API key lacks `scope` (see `with_scoped_api_key`), e.g. to show
+    /// `a***@example.com` instead of the full email. Applies to listings,
+    /// single records, relations, search results and CSV exports; facets of
+    /// the column are refused. Filters still match the real values.
+    pub fn mask_column<F>(
+        &mut self,
+        table_name: &str,
+        column: &str,
+        scope: &str,
+        mask: F,
+    ) -> anyhow::Result<()>
+    where
+        F: Fn(&Value) -> Value + Send + Sync + 'static,
+    {
+        let rule = ColumnMask {
+            scope: scope.to_string(),
+            mask: Arc::new(mask),
+        };
+        self.table_config(table_name, column)?
+            .masks
+            .insert(column.to_string(), rule);
+        Ok(())
+    }
+
+    /// Checks every value written to the column before it reaches the
+    /// database. Failing requests get `422 {"errors": {"age": "out of range"}}`
+    /// listing every failed column. POST and upsert also check columns left
+    /// out of the body (as `null`); PUT and PATCH only check what they change.
+    /// Several validators may be added to one column.
+    pub fn add_validator<F>(
+        &mut self,
+        table_name: &str,
+        column: &str,
+        check: F,
+    ) -> anyhow::Result<()>
+    where
+        F: Fn(&Value) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.table_config(table_name, column)?
+            .validators
+            .entry(column.to_string())
+            .or_default()
+            .push(Arc::new(check));
+        Ok(())
+    }
+
+    /// Addresses the table's records by `column` instead of `id` in
+    /// `/{table}/{id}` routes, e.g. a TEXT UUID or a natural key. Path
+    /// segments are compared as integers when the column is declared INTEGER
+    /// and as text otherwise.
+    pub fn primary_key(&mut self, table_name: &str, column: &str) -> anyhow::Result<()> {
+        self.table_config(table_name, column)?.primary_key = Some(column.to_string());
+        Ok(())
+    }
+
+    /// Serves the table under `/{alias}` instead of `/{table}`, e.g. the
+    /// `student_records_2024` table as `/students`. Queries still use the
+    /// real name. The alias may contain letters, digits, `_` and `-`, and
+    /// must not start with `_`.
+    pub fn route_alias(&mut self, table_name: &str, alias: &str) -> anyhow::Result<()> {
+        let valid = !alias.is_empty()
+            && !alias.starts_with('_')
+            && alias
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'));
+        if !valid {
+            return Err(anyhow::anyhow!("Invalid route alias: {}", alias));
+        }
+        self.table_settings(table_name)?.route = Some(alias.to_string());
+        Ok(())
+    }
+
+    /// Sorts listings of the table by `column` when the request has no
+    /// `_sort`, e.g. newest first with `db.default_sort("logs", "id", "desc")`.
+    /// `order` is `asc` or `desc`; `_order` and `_nulls` still apply, and an
+    /// explicit `_sort` replaces it.
+    pub fn default_sort(
+        &mut self,
+        table_name: &str,
+        column: &str,
+        order: &str,
+    ) -> anyhow::Result<()> {
+        let descending = match order.to_lowercase().as_str() {
+            "asc" => false,
+            "desc" => true,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Invalid sort order: {} (expected asc or desc)",
+                    order
+                ))
+            }
+        };
+        self.table_config(table_name, column)?.default_sort =
+            Some((column.to_string(), descending));
+        Ok(())
+    }
+
+    /// Documents a column. SQLite has no column comments, so the text is kept
+    /// here and returned as `description` by `GET /{table}/schema`.
+    pub fn describe_column(
+        &mut self,
+        table_name: &str,
+        column: &str,
+        description: &str,
+    ) -> anyhow::Result<()> {
+        self.table_config(table_name, column)?
+            .descriptions
+            .insert(column.to_string(), description.to_string());
+        Ok(())
+    }
+
+    /// Declares how a column's values are converted. A `ColumnKind::Json`
+    /// column stores written values as JSON text and returns them parsed, so
+    /// clients see `{"tags": [...]}` rather than an escaped string. A
+    /// `ColumnKind::Bool` column returns its 0/1 as `false`/`true` and accepts
+    /// booleans in writes and `?col=true` filters. A `ColumnKind::DateTime`
+    /// column refuses writes that aren't RFC3339 (`422`) and normalizes the
+    /// rest to UTC; filters on it may also use a plain date (`col_gte=2024-01-01`).
+    pub fn column_kind(
+        &mut self,
+        table_name: &str,
+        column: &str,
+        kind: ColumnKind,
+    ) -> anyhow::Result<()> {
+        self.table_config(table_name, column)?
+            .kinds
+            .insert(column.to_string(), kind);
+        Ok(())
+    }
+
+    /// Lets the server fill in the table's `created_at` (on insert) and
+    /// `updated_at` (on insert and update) columns as RFC3339 UTC strings.
+    /// Values sent by clients for these columns are overwritten.
+    pub fn auto_timestamps(&mut self, table_name: &str) -> anyhow::Result<()> {
+        self.table_settings(table_name)?.timestamps = true;
+        Ok(())
+    }
+
+    /// Indexes the given text columns in an FTS5 table named `{table}_fts` and
+    /// serves `GET /{table}/search?q=...` from it, best matches first.
+    /// Triggers keep the index in sync with every write; existing rows are
+    /// indexed right away. Call this after `create_table`.
+    pub fn enable_fts(&mut self, table_name: &str, columns: &[&str]) -> anyhow::Result<()> {
+        if !is_valid_identifier(table_name) {
+            return Err(anyhow::anyhow!("Invalid table name: {}", table_name));
+        }
+        if columns.is_empty() {
+            return Err(anyhow::anyhow!("At least one column is required"));
+        }
+        for col in columns {
+            if !is_valid_identifier(col) {
+                return Err(anyhow::anyhow!("Invalid column name: {}", col));
+            }
+        }
+
+        let t = table_name;
+        let fts = quote_ident(&format!("{}_fts", t));
+        let cols: Vec<String> = columns.iter().map(|c| quote_ident(c)).collect();
+        let cols = cols.join(", ");
+        let new_cols: Vec<String> = columns
+            .iter()
+            .map(|c| format!("new.{}", quote_ident(c)))
+            .collect();
+        let old_cols: Vec<String> = columns
+            .iter()
+            .map(|c| format!("old.{}", quote_ident(c)))
+            .collect();
+        let (new_cols, old_cols) = (new_cols.join(", "), old_cols.join(", "));
+
+        // The index refers to rows by rowid, which only an INTEGER PRIMARY
+        // KEY keeps stable (VACUUM may renumber any other rowid)
+        let conn = self.conn_for(t);
+        let conn = lock_conn(&conn);
+        let Some(key) = rowid_alias(&conn, t)? else {
+            return Err(anyhow::anyhow!(
+                "Full-text search needs an INTEGER PRIMARY KEY on '{}'",
+                t
+            ));
+        };
+
+        // External-content table: the text lives in `t`, the index in `fts`
+        let (name, t, id) = (t, quote_ident(t), quote_ident(&key));
+        let trigger = |suffix: &str| quote_ident(&format!("{}_fts_{}", name, suffix));
+        let (ai, ad, au) = (trigger("ai"), trigger("ad"), trigger("au"));
+        let sql = format!(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS {fts} USING fts5({cols}, content='{name}', content_rowid='{key}');
+             CREATE TRIGGER IF NOT EXISTS {ai} AFTER INSERT ON {t} BEGIN
+                 INSERT INTO {fts}(rowid, {cols}) VALUES (new.{id}, {new_cols});
+             END;
+             CREATE TRIGGER IF NOT EXISTS {ad} AFTER DELETE ON {t} BEGIN
+                 INSERT INTO {fts}({fts}, rowid, {cols}) VALUES ('delete', old.{id}, {old_cols});
+             END;
+             CREATE TRIGGER IF NOT EXISTS {au} AFTER UPDATE ON {t} BEGIN
+                 INSERT INTO {fts}({fts}, rowid, {cols}) VALUES ('delete', old.{id}, {old_cols});
+                 INSERT INTO {fts}(rowid, {cols}) VALUES (new.{id}, {new_cols});
+             END;
+             INSERT INTO {fts}({fts}) VALUES ('rebuild');"
+        );
+        conn.execute_batch(&sql)?;
+        drop(conn);
+
+        self.table_settings(name)?.full_text = true;
+        println!(
+            "🔎 Full-text search enabled for '{}' ({}).",
+            name,
+            columns.join(", ")
+        );
+        Ok(())
+    }
+
+    /// Makes `DELETE /{table}/{id}` set the row's `deleted_at` column to the
+    /// current time instead of removing it. Reads skip such rows unless
+    /// `?_with_deleted=true` is given. Adds a `deleted_at TEXT` column when
+    /// the table has none, so call this after `create_table`.
+    pub fn soft_delete(&mut self, table_name: &str) -> anyhow::Result<()> {
+        if !is_valid_identifier(table_name) {
+            return Err(anyhow::anyhow!("Invalid table name: {}", table_name));
+        }
+
+        let conn = self.conn_for(table_name);
+        let conn = lock_conn(&conn);
+        let columns = schema_columns(&conn, table_name)?;
+        if columns.is_empty() {
+            return Err(anyhow::anyhow!("Table '{}' does not exist", table_name));
+        }
+        if !columns.iter().any(|(c, _)| c == "deleted_at") {
+            conn.execute(
+                &format!(
+                    "ALTER TABLE {} ADD COLUMN deleted_at TEXT",
+                    quote_ident(table_name)
+                ),
+                [],
+            )?;
+        }
+        drop(conn);
+
+        self.table_settings(table_name)?.soft_delete = true;
+        Ok(())
+    }
+
+    /// Queues `POST /{table}` bodies and inserts them in batched transactions
+    /// from a background task; the request is answered with `202 Accepted`
+    /// right away. A batch is written once `batch_size` rows are waiting or
+    /// every `interval`, whichever comes first, and the queue is flushed when
+    /// the server shuts down. Rows that fail to insert are logged and skipped.
+    pub fn batch_writes(
+        &mut self,
+        table_name: &str,
+        batch_size: usize,
+        interval: Duration,
+    ) -> anyhow::Result<()> {
+        if batch_size == 0 {
+            return Err(anyhow::anyhow!("Batch size must be at least 1"));
+        }
+        self.table_settings(table_name)?.batch = Some(BatchConfig {
+            size: batch_size,
+            interval,
+        });
+        Ok(())
+    }
+
+    /// Declares that `child.column` references `parent.column`.
+    ///
+    /// `GET /{child}?_expand={parent}` then nests the matching parent row
+    /// under the `{parent}` key (or `null` when no parent exists), and
+    /// `GET /{parent}/{id}?_embed={child}` nests the matching child rows
+    /// under the `{child}` key as an array.
+    pub fn add_relation(&mut self, parent: &str, child: &str, column: &str) -> anyhow::Result<()> {
+        self.push_relation(parent, child, column, false)
+    }
+
+    /// Like `add_relation`, and `DELETE /{parent}/{id}` also deletes the
+    /// matching child rows (soft-deleting them if the child table uses
+    /// `soft_delete`), in the same transaction as the parent. For schemas
+    /// without `ON DELETE CASCADE`; both tables must share a database file.
+    pub fn add_cascading_relation(
+        &mut self,
+        parent: &str,
+        child: &str,
+        column: &str,
+    ) -> anyhow::Result<()> {
+        self.push_relation(parent, child, column, true)
+    }
+
+    fn push_relation(
+        &mut self,
+        parent: &str,
+        child: &str,
+        column: &str,
+        cascade: bool,
+    ) -> anyhow::Result<()> {
+        for name in [parent, child, column] {
+            if !is_valid_identifier(name) {
+                return Err(anyhow::anyhow!("Invalid identifier: {}", name));
+            }
+        }
+
+        self.relations.push(Relation {
+            parent: parent.to_string(),
+            child: child.to_string(),
+            column: column.to_string(),
+            cascade,
+        });
+        Ok(())
+    }
+
+    /// Returns the mutable config of a table after validating both identifiers.
+    fn table_config(&mut self, table_name: &str, column: &str) -> anyhow::Result<&mut TableConfig> {
+        if !is_valid_identifier(column) {
+            return Err(anyhow::anyhow!("Invalid column name: {}", column));
+        }
+        self.table_settings(table_name)
+    }
+
+    /// Returns the mutable config of a table after validating its name.
+    fn table_settings(&mut self, table_name: &str) -> anyhow::Result<&mut TableConfig> {
+        if !is_valid_identifier(table_name) {
+            return Err(anyhow::anyhow!("Invalid table name: {}", table_name));
+        }
+        Ok(self
+            .table_configs
+            .entry(table_name.to_string())
+            .or_default())
+    }
+
+    /// Exposes an existing table to the API without creating it.
+    pub fn expose_table(&mut self, table_name: &str) -> anyhow::Result<()> {
+        if !is_valid_identifier(table_name) {
+            return Err(anyhow::anyhow!("Invalid table name: {}", table_name));
+        }
+
+        let conn = self.conn_for(table_name);
+        let exists: bool = lock_conn(&conn).query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type IN ('table', 'view') AND name = ?",
+            [table_name],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Err(anyhow::anyhow!("Table not found: {}", table_name));
+        }
+
+        self.exposed_tables.push(table_name.to_string());
+        println!("✅ Table '{}' exposed to API.", table_name);
+        Ok(())
+    }
+
+    /// Creates a table and automatically exposes it to the API.
+    pub fn create_table(&mut self, table_name: &str, columns: &str) -> anyhow::Result<()> {
+        // Security check for table name
+        if !is_valid_identifier(table_name) {
+            return Err(anyhow::anyhow!("Invalid table name: {}", table_name));
+        }
+        check_column_defs(columns, self.strict_column_types)?;
+
+        if self.strict_identifiers {
+            if !is_snake_case_identifier(table_name) {
+                return Err(anyhow::anyhow!(
+                    "Table name is not snake_case: {}",
+                    table_name
+                ));
+            }
+            for column in column_names(columns) {
+                if !is_snake_case_identifier(&column) {
+                    return Err(anyhow::anyhow!("Column name is not snake_case: {}", column));
+                }
+            }
+        }
+
+        let sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} ({})",
+            quote_ident(table_name),
+            columns
+        );
+
+        let conn = self.conn_for(table_name);
+        lock_conn(&conn).execute(&sql, [])?;
+
+        self.exposed_tables.push(table_name.to_string());
+        println!("✅ Table '{}' created and exposed to API.", table_name);
+        Ok(())
+    }
+
+    /// Creates an index on `columns` of `table` (`CREATE INDEX IF NOT EXISTS`),
+    /// so filters and sorts on them don't scan the whole table. With `unique`,
+    /// duplicate values in those columns are rejected.
+    pub fn create_index(
+        &mut self,
+        name: &str,
+        table_name: &str,
+        columns: &[&str],
+        unique: bool,
+    ) -> anyhow::Result<()> {
+        for ident in [name, table_name].iter().chain(columns) {
+            if !is_valid_identifier(ident) || ident.is_empty() {
+                return Err(anyhow::anyhow!("Invalid identifier: {}", ident));
+            }
+        }
+        if columns.is_empty() {
+            return Err(anyhow::anyhow!("An index needs at least one column"));
+        }
+
+        let sql = format!(
+            "CREATE {}INDEX IF NOT EXISTS {} ON {} ({})",
+            if unique { "UNIQUE " } else { "" },
+            quote_ident(name),
+            quote_ident(table_name),
+            quoted_list(columns)
+        );
+        let conn = self.conn_for(table_name);
+        lock_conn(&conn).execute(&sql, [])?;
+        Ok(())
+    }
+
+    /// Drops the table (and its full-text index) and stops exposing it.
+    /// Routes are built when the server starts, so call this before
+    /// `run_server`.
+    pub fn drop_table(&mut self, table_name: &str) -> anyhow::Result<()> {
+        if !is_valid_identifier(table_name) {
+            return Err(anyhow::anyhow!("Invalid table name: {}", table_name));
+        }
+
+        let conn = self.conn_for(table_name);
+        let conn = lock_conn(&conn);
+        conn.execute(
+            &format!("DROP TABLE IF EXISTS {}", quote_ident(table_name)),
+            [],
+        )?;
+        if self
+            .table_configs
+            .get(table_name)
+            .is_some_and(|c| c.full_text)
+        {
+            let fts = quote_ident(&format!("{}_fts", table_name));
+            conn.execute(&format!("DROP TABLE IF EXISTS {}", fts), [])?;
+        }
+        drop(conn);
+
+        self.exposed_tables.retain(|t| t != table_name);
+        self.table_configs.remove(table_name);
+        self.relations
+            .retain(|r| r.parent != table_name && r.child != table_name);
+        println!("🗑️ Table '{}' dropped.", table_name);
+        Ok(())
+    }
+
+    /// Inserts a row without going through HTTP and returns its rowid
+    /// (handy for seeding and tests). Values keep their JSON types, and hashed
+    /// columns and `auto_timestamps` are handled like in `POST /{table}`.
+    pub fn insert(&self, table_name: &str, row: &Value) -> anyhow::Result<i64> {
+        if !is_valid_identifier(table_name) {
+            return Err(anyhow::anyhow!("Invalid table name: {}", table_name));
+        }
+        let mut obj = match row {
+            Value::Object(obj) if !obj.is_empty() => obj.clone(),
+            _ => return Err(anyhow::anyhow!("Row must be a non-empty JSON object")),
+        };
+        for key in obj.keys() {
+            if !is_valid_identifier(key) {
+                return Err(anyhow::anyhow!("Invalid column name: {}", key));
+            }
+        }
+
+        let config = self.table_config_for(table_name);
+        if config.timestamps {
+            let now = now_rfc3339(self.clock.as_ref());
+            for col in ["created_at", "updated_at"] {
+                obj.insert(col.to_string(), Value::String(now.clone()));
+            }
+        }
+
+        let mut values = Vec::with_capacity(obj.len());
+        for (key, value) in &obj {
+            match value {
+                Value::String(plain) if config.hashed_columns.contains(key) => {
+                    values.push(rusqlite::types::Value::Text(hash_secret(plain)?))
+                }
+                other => values.push(json_to_sql(&stored_value(&config, key, other))),
+            }
+        }
+
+        let keys: Vec<&str> = obj.keys().map(|k| k.as_str()).collect();
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote_ident(table_name),
+            quoted_list(&keys),
+            vec!["?"; keys.len()].join(", ")
+        );
+
+        let conn = self.conn_for(table_name);
+        let conn = lock_conn(&conn);
+        conn.execute(&sql, rusqlite::params_from_iter(values.iter()))?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Hands the main database connection to `f` while holding its lock, for
+    /// migrations or queries the generated API doesn't cover. Keep `f` short:
+    /// requests on the same database wait for it. Tables mapped with
+    /// `map_table_to_db` live on other connections.
+    pub fn with_connection<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&Connection) -> R,
+    {
+        f(&lock_conn(&self.conn))
+    }
+
+    /// Runs a read query on the main database and returns the rows as JSON
+    /// objects. The SQL is trusted (it is not validated), so never build it
+    /// from user input; pass values through `params` instead.
+    pub fn query(&self, sql: &str, params: &[Value]) -> anyhow::Result<Vec<Value>> {
+        let conn = lock_conn(&self.conn);
+        let mut stmt = conn.prepare(sql)?;
+        let values: Vec<rusqlite::types::Value> = params.iter().map(json_to_sql).collect();
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(values.iter()), |row| {
+                Ok(row_to_json(
+                    row,
+                    &TableConfig::default(),
+                    &Scopes::default(),
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<Value>>>()?;
+        Ok(rows)
+    }
+
+    /// Starts the server and generates routes.
+    pub async fn run_server(self, port: u16) -> anyhow::Result<()> {
+        self.run_server_with_shutdown(port, std::future::pending())
+            .await
+    }
+
+    /// Starts the server and shuts it down gracefully once `signal` completes.
+    ///
+    /// New connections are refused after the signal; in-flight requests are
+    /// allowed to finish, bounded by `with_drain_timeout` when it is set.
+    pub async fn run_server_with_shutdown<F>(self, port: u16, signal: F) -> anyhow::Result<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.bind(port).await?.serve_with_shutdown(signal).await
+    }
+
+    /// Builds the routes and middleware without serving them, e.g. to drive
+    /// the API in tests with `tower::ServiceExt::oneshot` (no port, no
+    /// startup sleep) or to nest it in a larger axum app. Must be called
+    /// inside a tokio runtime. Rows queued by `batch_writes` are only
+    /// guaranteed to be written once the router is dropped and
+    /// `WriteQueues::flush` has completed; a runtime that shuts down earlier
+    /// loses them.
+    pub fn into_router(self) -> anyhow::Result<(Router, WriteQueues)> {
+        let (app, flushers) = self.build_router()?;
+        Ok((app, WriteQueues { flushers }))
+    }
+
+    /// Builds the routes and binds the listener without serving yet. Pass port
+    /// `0` to let the OS pick a free one and read it from `local_addr`.
+    pub async fn bind(self, port: u16) -> anyhow::Result<BoundServer> {
+        let (app, flushers) = self.build_router()?;
+        let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+        Ok(BoundServer {
+            local_addr: listener.local_addr()?,
+            listener,
+            app,
+            queues: WriteQueues { flushers },
+            drain_timeout: self.drain_timeout,
+        })
+    }
+
+    /// Starts the server over HTTPS with the PEM certificate chain and private
+    /// key at the given paths (feature `tls`). Fails before binding if either
+    /// file is missing or can't be parsed.
+    #[cfg(feature = "tls")]
+    pub async fn run_server_tls(
+        self,
+        port: u16,
+        cert_path: impl AsRef<std::path::Path>,
+        key_path: impl AsRef<std::path::Path>,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        let (cert_path, key_path) = (cert_path.as_ref(), key_path.as_ref());
+        for (what, path) in [("certificate", cert_path), ("private key", key_path)] {
+            if !path.is_file() {
+                return Err(anyhow::anyhow!(
+                    "TLS {} not found: {}",
+                    what,
+                    path.display()
+                ));
+            }
+        }
+        let tls = axum_server::tls_rustls::RustlsConfig::from_pem_file(cert_path, key_path)
+            .await
+            .with_context(|| {
+                format!(
+                    "Invalid TLS certificate or key ({}, {})",
+                    cert_path.display(),
+                    key_path.display()
+                )
+            })?;
+
+        let (app, flushers) = self.build_router()?;
+        let addr = SocketAddr::from(([0, 0, 0, 0], port));
+        println!("🔒 Easy-DB Server is running: https://{}", addr);
+        axum_server::bind_rustls(addr, tls)
+            .serve(app.into_make_service_with_connect_info::<ClientAddr>())
+            .await?;
+        WriteQueues { flushers }.flush().await;
+        Ok(())
+    }
+
+    fn table_config_for(&self, table_name: &str) -> TableConfig {
+        self.table_configs
+            .get(table_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The path segments of the exposed tables, which must be distinct
+    fn routes(&self) -> anyhow::Result<Vec<String>> {
+        let mut routes: Vec<String> = Vec::new();
+        for table in &self.exposed_tables {
+            let route = self.route_for(table);
+            if routes.contains(&route) {
+                return Err(anyhow::anyhow!("Two tables are served under /{}", route));
+            }
+            routes.push(route);
+        }
+        Ok(routes)
+    }
+
+    /// The path segment a table is served under
+    fn route_for(&self, table_name: &str) -> String {
+        self.table_configs
+            .get(table_name)
+            .and_then(|config| config.route.clone())
+            .unwrap_or_else(|| table_name.to_string())
+    }
+
+    fn related_table(&self, table_name: &str, column: &str) -> RelatedTable {
+        RelatedTable {
+            name: table_name.to_string(),
+            column: column.to_string(),
+            conn: self.conn_for(table_name),
+            config: self.table_config_for(table_name),
+        }
+    }
+
+    /// Builds the router with the generated routes for every exposed table,
+    /// along with the background tasks that flush the write queues.
+    fn build_router(&self) -> anyhow::Result<(Router, Vec<tokio::task::JoinHandle<()>>)> {
+        let mut flushers = Vec::new();
+        let routes = self.routes()?;
+        // The health check covers every database file, including mapped ones
+        let mut conns = vec![Arc::clone(&self.conn)];
+        conns.extend(self.table_dbs.values().map(|m| Arc::clone(&m.conn)));
+
+        // Every write, whichever route or task made it, is published to subscribers
+        let (changes, _) = broadcast::channel(256);
+        for conn in &conns {
+            let tx = changes.clone();
+            let hook = move |action, _db: &str, table: &str, id| {
+                let op = match action {
+                    rusqlite::hooks::Action::SQLITE_INSERT => "insert",
+                    rusqlite::hooks::Action::SQLITE_UPDATE => "update",
+                    rusqlite::hooks::Action::SQLITE_DELETE => "delete",
+                    _ => return,
+                };
+                let _ = tx.send(ChangeEvent {
+                    table: table.to_string(),
+                    op,
+                    id,
+                });
+            };
+            if let Err(e) = lock_conn(conn).update_hook(Some(hook)) {
+                tracing::warn!("Change notifications disabled: {}", e);
+            }
+        }
+        let conns = Arc::new(conns);
+        let mut app =
+            Router::new().route(HEALTH_PATH, get(move || handle_health(Arc::clone(&conns))));
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            if routes.iter().any(|t| t == "metrics")
+                || self
+                    .mounts
+                    .iter()
+                    .any(|(p, _)| p.split('/').nth(1) == Some("metrics"))
+            {
+                return Err(anyhow::anyhow!(
+                    "A table or mount named 'metrics' conflicts with the metrics endpoint"
+                ));
+            }
+            let metrics = Arc::clone(metrics);
+            app = app.route(
+                METRICS_PATH,
+                get(move || handle_metrics(Arc::clone(&metrics))),
+            );
+        }
+
+        // Dynamically add routes for each table
+        let mut states = HashMap::new();
+        for table in &self.exposed_tables {
+            let t = table.clone();
+            let route = self.route_for(table);
+            let parents = self
+                .relations
+                .iter()
+                .filter(|r| &r.child == table)
+                .map(|r| self.related_table(&r.parent, &r.column))
+                .collect();
+            let children = self
+                .relations
+                .iter()
+                .filter(|r| &r.parent == table)
+                .map(|r| self.related_table(&r.child, &r.column))
+                .collect();
+            let cascades: Vec<RelatedTable> = self
+                .relations
+                .iter()
+                .filter(|r| &r.parent == table && r.cascade)
+                .map(|r| self.related_table(&r.child, &r.column))
+                .collect();
+            if let Some(child) = cascades
+                .iter()
+                .find(|c| !Arc::ptr_eq(&c.conn, &self.conn_for(table)))
+            {
+                return Err(anyhow::anyhow!(
+                    "Cascading relation {} -> {} spans two database files",
+                    table,
+                    child.name
+                ));
+            }
+            let config = self.table_config_for(table);
+            let queue = config.batch.map(|batch| {
+                let (tx, rx) = mpsc::channel(batch.size * 4);
+                flushers.push(tokio::spawn(run_write_queue(
+                    t.clone(),
+                    self.conn_for(table),
+                    config.clone(),
+                    self.busy_retry,
+                    rx,
+                )));
+                tx
+            });
+            let columns = schema_columns(&lock_conn(&self.conn_for(table)), table)?;
+            let required = required_columns(&lock_conn(&self.conn_for(table)), table)?;
+            let unique = unique_columns(&lock_conn(&self.conn_for(table)), table)?;
+            let primary_key = config
+                .primary_key
+                .clone()
+                .unwrap_or_else(|| "id".to_string());
+            // SQLite's affinity rules: any declared type containing INT
+            let integer_key = match columns.iter().find(|(name, _)| *name == primary_key) {
+                Some((_, decl)) => decl.to_uppercase().contains("INT"),
+                None if config.primary_key.is_some() => {
+                    return Err(anyhow::anyhow!(
+                        "Primary key column '{}' not found in table '{}'",
+                        primary_key,
+                        table
+                    ))
+                }
+                None => true,
+            };
+            if let Some((column, _)) = &config.default_sort {
+                if !columns.iter().any(|(name, _)| name == column) {
+                    return Err(anyhow::anyhow!(
+                        "Default sort column '{}' not found in table '{}'",
+                        column,
+                        table
+                    ));
+                }
+            }
+            // SQLite's affinity rules again: TEXT unless INT comes first
+            let text_columns = columns
+                .iter()
+                .filter(|(name, decl)| {
+                    let decl = decl.to_uppercase();
+                    !decl.contains("INT")
+                        && ["CHAR", "CLOB", "TEXT"].iter().any(|t| decl.contains(t))
+                        && !config.hidden_columns.contains(name)
+                        && !config.hashed_columns.contains(name)
+                        && !config.masks.contains_key(name)
+                })
+                .map(|(name, _)| name.clone())
+                .collect();
+            let state = Arc::new(TableState {
+                name: t.clone(),
+                conn: self.conn_for(table),
+                config,
+                clock: Arc::clone(&self.clock),
+                strict_hidden_columns: self.strict_hidden_columns,
+                no_content_deletes: self.no_content_deletes,
+                csv_null: self.csv_null.clone(),
+                busy_retry: self.busy_retry,
+                scan_guard: self.scan_guard,
+                columns: columns.into_iter().map(|(name, _)| name).collect(),
+                required,
+                unique,
+                text_columns,
+                primary_key,
+                integer_key,
+                changes: changes.clone(),
+                queue,
+                parents,
+                children,
+                cascades,
+            });
+            states.insert(route.clone(), Arc::clone(&state));
+
+            let (collection_allow, item_allow) = if self.read_only {
+                ("GET, HEAD, OPTIONS", "GET, HEAD, OPTIONS")
+            } else {
+                (
+                    "GET, HEAD, POST, PUT, DELETE, OPTIONS",
+                    "GET, HEAD, PUT, PATCH, DELETE, OPTIONS",
+                )
+            };
+
+            app = app
+                .route(
+                    &format!("/{}", route),
+                    get({
+                        let s = Arc::clone(&state);
+                        move |sc, u, h, q| handle_list(State(s), sc, u, h, q)
+                    })
+                    .options(move || handle_options(collection_allow)),
+                )
+                .route(
+                    &format!("/{}/facets", route),
+                    get({
+                        let s = Arc::clone(&state);
+                        move |sc, q| handle_facets(State(s), sc, q)
+                    }),
+                );
+            if self.introspection {
+                app = app
+                    .route(
+                        &format!("/{}/schema", route),
+                        get({
+                            let s = Arc::clone(&state);
+                            move || handle_schema(State(s))
+                        }),
+                    )
+                    .route(
+                        &format!("/{}/ddl", route),
+                        get({
+                            let s = Arc::clone(&state);
+                            move || handle_ddl(State(s))
+                        }),
+                    );
+            } else {
+                // Answered here so the paths aren't taken for `/{table}/{id}` lookups
+                app = app
+                    .route(&format!("/{}/schema", route), any(handle_hidden))
+                    .route(&format!("/{}/ddl", route), any(handle_hidden));
+            }
+            app = app
+                .route(
+                    &format!("/{}/subscribe", route),
+                    get({
+                        let s = Arc::clone(&state);
+                        move |ws| handle_subscribe(State(s), ws)
+                    }),
+                )
+                .route(
+                    &format!("/{}.csv", route),
+                    get({
+                        let s = Arc::clone(&state);
+                        move |sc, q| handle_export_csv(State(s), sc, q)
+                    }),
+                );
+
+            // FIX: Changed from /:id to /{id} for Axum 0.7 compatibility
+            // Note: We use double braces {{id}} to escape them in format! macro
+            let id_path = format!("/{}/{{id}}", route);
+            if state.config.full_text {
+                app = app.route(
+                    &format!("/{}/search", route),
+                    get({
+                        let s = Arc::clone(&state);
+                        move |sc, q| handle_search(State(s), sc, q)
+                    }),
+                );
+            }
+
+            let get_one = get({
+                let s = Arc::clone(&state);
+                move |sc, p, h, q| handle_get_one(State(s), sc, p, h, q)
+            })
+            .options(move || handle_options(item_allow));
+
+            if self.read_only {
+                // Write routes are not generated; POST on `/{table}` already gets
+                // axum's 405, the write-only paths answer 405 instead of 404.
+                app = app
+                    .route(&format!("/{}/upsert", route), any(handle_read_only))
+                    .route(&format!("/{}/import", route), any(handle_read_only))
+                    .route(&id_path, get_one.fallback(handle_read_only));
+                continue;
+            }
+
+            app = app
+                .route(&id_path, get_one)
+                .route(
+                    &format!("/{}", route),
+                    post({
+                        let s = Arc::clone(&state);
+                        move |u, j| handle_post(State(s), u, j)
+                    })
+                    .put({
+                        let s = Arc::clone(&state);
+                        move |q, h, j| handle_bulk_put(State(s), q, h, j)
+                    })
+                    .delete({
+                        let s = Arc::clone(&state);
+                        move |q, h| handle_bulk_delete(State(s), q, h)
+                    }),
+                )
+                .route(
+                    &format!("/{}/upsert", route),
+                    post({
+                        let s = Arc::clone(&state);
+                        move |q, j| handle_upsert(State(s), q, j)
+                    }),
+                )
+                .route(
+                    &format!("/{}/import", route),
+                    post({
+                        let s = Arc::clone(&state);
+                        move |q, h, b| handle_import_csv(State(s), q, h, b)
+                    }),
+                )
+                .route(
+                    &id_path,
+                    put({
+                        let s = Arc::clone(&state);
+                        move |p, q, j| handle_put(State(s), p, q, j)
+                    }),
+                )
+                .route(
+                    &id_path,
+                    patch({
+                        let s = Arc::clone(&state);
+                        move |p, q, h, b| handle_patch(State(s), p, q, h, b)
+                    }),
+                )
+                .route(
+                    &id_path,
+                    delete({
+                        let s = Arc::clone(&state);
+                        move |p| handle_delete(State(s), p)
+                    }),
+                );
+        }
+
+        if !self.read_only {
+            let states = Arc::new(states);
+            app = app.route(
+                BATCH_PATH,
+                post(move |j| handle_batch(Arc::clone(&states), j)),
+            );
+        }
+
+        if let Some(dir) = &self.backup_dir {
+            if self.api_keys.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "The backup endpoint requires an API key (with_api_key)"
+                ));
+            }
+            let backup = self.backup_fn();
+            let (dir, name, clock) = (dir.clone(), self.db_name.clone(), Arc::clone(&self.clock));
+            app = app.route(
+                BACKUP_PATH,
+                post(move || {
+                    let dest = dir.join(format!(
+                        "{}-{}.db",
+                        name,
+                        now_rfc3339(clock.as_ref()).replace(':', "-")
+                    ));
+                    handle_backup(Arc::clone(&backup), dest)
+                }),
+            );
+        }
+
+        // Mounted databases bring their own routes and middleware
+        let mut mounted: HashSet<&str> = HashSet::new();
+        for (prefix, db) in &self.mounts {
+            let segment = prefix[1..].split('/').next().unwrap_or_default();
+            let taken = routes.iter().any(|t| t == segment)
+                || HEALTH_PATH[1..] == *segment
+                || BATCH_PATH[1..] == *segment
+                || BACKUP_PATH[1..] == *segment
+                || !mounted.insert(segment);
+            if taken {
+                return Err(anyhow::anyhow!(
+                    "Mount prefix '{}' overlaps another route",
+                    prefix
+                ));
+            }
+            let (router, db_flushers) = db.build_router()?;
+            flushers.extend(db_flushers);
+            app = app.nest(prefix, router);
+        }
+
+        // Checked here, since `Router::route` panics on an overlapping path
+        #[cfg(feature = "metrics")]
+        let metrics = self.metrics.as_ref().map(|_| &METRICS_PATH[1..]);
+        #[cfg(not(feature = "metrics"))]
+        let metrics: Option<&str> = None;
+        let mut custom: HashSet<&str> = HashSet::new();
+        for (path, route) in &self.extra_routes {
+            let segment = path
+                .strip_prefix('/')
+                .map(|p| p.split('/').next().unwrap_or_default());
+            let Some(segment) = segment else {
+                return Err(anyhow::anyhow!("Route '{}' must start with '/'", path));
+            };
+            let taken = routes.iter().any(|t| t == segment)
+                || [HEALTH_PATH, BATCH_PATH, BACKUP_PATH]
+                    .iter()
+                    .any(|reserved| reserved[1..] == *segment)
+                || metrics == Some(segment)
+                || mounted.contains(segment)
+                || segment.starts_with('{')
+                || !custom.insert(path);
+            if taken {
+                return Err(anyhow::anyhow!("Route '{}' overlaps another route", path));
+            }
+            app = app.route(path, route.clone());
+        }
+
+        // Without introspection, a 404 doesn't reveal which tables exist
+        let tables = Arc::new(routes);
+        let introspection = self.introspection;
+        app = app.fallback(move |uri| handle_not_found(tables, introspection, uri));
+
+        Ok((self.apply_layers(app), flushers))
+    }
+
+    /// Wraps the routes in the middleware stack. From the outside in:
+    ///
+    /// 1. Tracing - sees and times every request, including rejected ones.
+    /// 2. Metrics (if enabled) - counts and times every request as well.
+    /// 3. Compression (if enabled) - encodes every response body, errors too.
+    /// 4. CORS - answers preflight requests (which carry no credentials) and
+    ///    adds CORS headers to error responses as well.
+    /// 5. API-key auth - rejects unauthenticated requests before anything that
+    ///    consumes resources, such as rate-limit budget or database access.
+    /// 6. Rate limiting (if enabled) - per client IP.
+    /// 7. Route handlers, nested under the `with_prefix` prefix if one is set,
+    ///    with request bodies capped by `with_max_body_size`.
+    ///
+    /// `Router::layer` wraps what is already there, so layers are added
+    /// innermost first.
+    fn apply_layers(&self, mut app: Router) -> Router {
+        app = app.layer(axum::extract::DefaultBodyLimit::max(self.max_body_size));
+
+        if !self.prefix.is_empty() {
+            app = Router::new().nest(&self.prefix, app);
+        }
+
+        let health_path: Arc<str> = format!("{}{}", self.prefix, HEALTH_PATH).into();
+        if let Some((requests, per)) = self.rate_limit {
+            let limiter = Arc::new(RateLimiter::new(requests, per));
+            let health_path = Arc::clone(&health_path);
+            app = app.layer(axum::middleware::from_fn(move |req, next| {
+                rate_limit(Arc::clone(&limiter), Arc::clone(&health_path), req, next)
+            }));
+        }
+
+        if !self.api_keys.is_empty() {
+            let keys = Arc::new(self.api_keys.clone());
+            app = app.layer(axum::middleware::from_fn(move |req, next| {
+                require_api_key(Arc::clone(&keys), Arc::clone(&health_path), req, next)
+            }));
+        }
+
+        // CORS: Allow requests from anywhere unless `with_cors` says otherwise.
+        // The CORS layer treats every OPTIONS as a preflight, so plain ones are
+        // routed around it to reach the `Allow` handlers.
+        let plain = app.clone();
+        app = app
+            .layer(self.cors.clone())
+            .layer(axum::middleware::from_fn(move |req, next| {
+                bypass_cors_for_options(plain.clone(), req, next)
+            }));
+
+        if self.compression {
+            app = app.layer(tower_http::compression::CompressionLayer::new());
+        }
+
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            let metrics = Arc::clone(metrics);
+            let tables: Arc<HashSet<String>> = Arc::new(
+                self.exposed_tables
+                    .iter()
+                    .map(|t| self.route_for(t))
+                    .collect(),
+            );
+            let prefix: Arc<str> = self.prefix.clone().into();
+            app = app.layer(axum::middleware::from_fn(move |req, next| {
+                record_metrics(
+                    Arc::clone(&metrics),
+                    Arc::clone(&tables),
+                    Arc::clone(&prefix),
+                    req,
+                    next,
+                )
+            }));
+        }
+
+        if self.logging {
+            app = app.layer(
+                TraceLayer::new_for_http()
+                    .make_span_with(DefaultMakeSpan::new().level(tracing::Level::INFO))
+                    .on_response(
+                        DefaultOnResponse::new()
+                            .level(tracing::Level::INFO)
+                            .latency_unit(tower_http::LatencyUnit::Millis),
+                    ),
+            );
+        }
+        app
+    }
+}
+
+/// Middleware: sends `OPTIONS` requests that are not CORS preflights (no
+/// `Access-Control-Request-Method`) straight to the router.
+async fn bypass_cors_for_options(
+    plain: Router,
+    req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use tower::ServiceExt;
+
+    let preflight = req
+        .headers()
+        .contains_key(axum::http::header::ACCESS_CONTROL_REQUEST_METHOD);
+    if req.method() == axum::http::Method::OPTIONS && !preflight {
+        plain.oneshot(req).await.unwrap_or_else(|e| match e {})
+    } else {
+        next.run(req).await
+    }
+}
+
+// --- AUTHENTICATION ---
+
+/// Middleware: lets the request through only if it carries a known API key.
+async fn require_api_key(
+    keys: Arc<Vec<ApiKey>>,
+    health_path: Arc<str>,
+    mut req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    // Liveness probes usually can't send credentials
+    if req.uri().path() == &*health_path {
+        return next.run(req).await;
+    }
+
+    let headers = req.headers();
+    let provided = headers
+        .get("x-api-key")
+        .and_then(|v| v.to_str().ok())
+        .or_else(|| {
+            headers
+                .get(axum::http::header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "))
+        });
+
+    let matched = provided.and_then(|provided| {
+        keys.iter()
+            .find(|k| constant_time_eq(k.key.as_bytes(), provided.as_bytes()))
+    });
+    match matched {
+        Some(key) => {
+            req.extensions_mut().insert(Scopes(key.scopes.clone()));
+            next.run(req).await
+        }
+        None => (
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({"error": "Unauthorized"})),
+        )
+            .into_response(),
+    }
+}
+
+// --- RATE LIMITING ---
+
+/// Token buckets per client IP: `capacity` tokens, refilled at `refill` per second.
+struct RateLimiter {
+    capacity: f64,
+    refill: f64,
+    buckets: Mutex<HashMap<std::net::IpAddr, (f64, std::time::Instant)>>,
+}
+
+/// Buckets kept before full (idle) ones are dropped
+const RATE_LIMIT_MAX_CLIENTS: usize = 10_000;
+
+impl RateLimiter {
+    fn new(requests: u32, per: Duration) -> Self {
+        let capacity = f64::from(requests);
+        Self {
+            capacity,
+            refill: capacity / per.as_secs_f64().max(f64::EPSILON),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a token for `ip`, or returns how long until one is available
+    fn acquire(&self, ip: std::net::IpAddr) -> Result<(), Duration> {
+        let now = std::time::Instant::now();
+        let mut buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if buckets.len() >= RATE_LIMIT_MAX_CLIENTS {
+            let (capacity, refill) = (self.capacity, self.refill);
+            buckets.retain(|_, (tokens, last)| {
+                *tokens + now.duration_since(*last).as_secs_f64() * refill < capacity
+            });
+        }
+
+        let (tokens, last) = buckets.entry(ip).or_insert((self.capacity, now));
+        *tokens =
+            (*tokens + now.duration_since(*last).as_secs_f64() * self.refill).min(self.capacity);
+        *last = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - *tokens) / self.refill))
+        }
+    }
+}
+
+/// Peer address of a connection, recorded by `run_server` and `run_server_tls`
+#[derive(Clone, Copy)]
+struct ClientAddr(SocketAddr);
+
+impl axum::extract::connect_info::Connected<axum::serve::IncomingStream<'_, DrainListener>>
+    for ClientAddr
+{
+    fn connect_info(stream: axum::serve::IncomingStream<'_, DrainListener>) -> Self {
+        ClientAddr(*stream.remote_addr())
+    }
+}
+
+#[cfg(feature = "tls")]
+impl axum::extract::connect_info::Connected<SocketAddr> for ClientAddr {
+    fn connect_info(addr: SocketAddr) -> Self {
+        ClientAddr(addr)
+    }
+}
+
+/// Middleware: answers `429` once the client's IP has used up its budget.
+/// Without connection info (e.g. the router served by other means) every
+/// request shares one bucket.
+async fn rate_limit(
+    limiter: Arc<RateLimiter>,
+    health_path: Arc<str>,
+    req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    if req.uri().path() == &*health_path {
+        return next.run(req).await;
+    }
+    let extensions = req.extensions();
+    let ip = extensions
+        .get::<axum::extract::ConnectInfo<ClientAddr>>()
+        .map(|info| info.0 .0.ip())
+        .or_else(|| {
+            extensions
+                .get::<axum::extract::ConnectInfo<SocketAddr>>()
+                .map(|info| info.0.ip())
+        })
+        .unwrap_or(std::net::IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED));
+
+    match limiter.acquire(ip) {
+        Ok(()) => next.run(req).await,
+        Err(wait) => {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(axum::http::header::RETRY_AFTER, retry_after.to_string())],
+                Json(serde_json::json!({"error": "Too many requests"})),
+            )
+                .into_response()
+        }
+    }
+}
+
+// Compares secrets without returning early, so timing doesn't reveal how much matched.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Words that start a table constraint rather than a column definition
+const TABLE_CONSTRAINTS: [&str; 5] = ["PRIMARY", "UNIQUE", "CHECK", "FOREIGN", "CONSTRAINT"];
+
+/// Extracts the column names from a column definition string such as
+/// `id INTEGER PRIMARY KEY, name TEXT`, skipping table constraints.
+fn column_names(columns: &str) -> Vec<String> {
+    split_top_level(columns)
+        .iter()
+        .filter_map(|def| def.split_whitespace().next())
+        .filter(|first| !TABLE_CONSTRAINTS.contains(&first.to_uppercase().as_str()))
+        .map(|first| {
+            first
+                .trim_matches(|c| matches!(c, '"' | '`' | '[' | ']'))
+                .to_string()
+        })
+        .collect()
+}
+
+/// Type names SQLite documents, plus common ones from other databases such as
+/// `TIMESTAMP`, `JSON` and `UUID` (first word only, e.g. `VARCHAR` of `VARCHAR(20)`)
+const COLUMN_TYPES: [&str; 35] = [
+    "INT",
+    "INTEGER",
+    "TINYINT",
+    "SMALLINT",
+    "MEDIUMINT",
+    "BIGINT",
+    "UNSIGNED",
+    "INT2",
+    "INT8",
+    "BOOL",
+    "CHAR",
+    "CHARACTER",
+    "VARCHAR",
+    "VARYING",
+    "NCHAR",
+    "NATIVE",
+    "NVARCHAR",
+    "TEXT",
+    "STRING",
+    "CLOB",
+    "BLOB",
+    "REAL",
+    "DOUBLE",
+    "FLOAT",
+    "NUMERIC",
+    "DECIMAL",
+    "BOOLEAN",
+    "DATE",
+    "DATETIME",
+    "TIME",
+    "TIMESTAMP",
+    "JSON",
+    "JSONB",
+    "UUID",
+    "ANY",
+];
+
+/// Words that may follow a column name in place of a type
+const COLUMN_CONSTRAINTS: [&str; 11] = [
+    "PRIMARY",
+    "NOT",
+    "NULL",
+    "UNIQUE",
+    "CHECK",
+    "DEFAULT",
+    "COLLATE",
+    "REFERENCES",
+    "GENERATED",
+    "AS",
+    "CONSTRAINT",
+];
+
+/// Helper: Rejects a `create_table` column list that could smuggle in more
+/// SQL (`;`, comments, unbalanced parentheses or quotes) or that names a
+/// column or type badly. A type name outside `COLUMN_TYPES`, such as
+/// `age INTEGR` (which SQLite gives NUMERIC affinity), is only warned
+/// about unless `strict_types` is set.
+fn check_column_defs(columns: &str, strict_types: bool) -> anyhow::Result<()> {
+    let invalid = |why: &str| anyhow::anyhow!("Invalid column definitions ({}): {}", why, columns);
+
+    let mut quote = None;
+    let mut depth = 0i32;
+    let mut chars = columns.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(close), _) if c == close => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '[') => quote = Some(']'),
+            (None, '(') => depth += 1,
+            (None, ')') => {
+                depth -= 1;
+                if depth < 0 {
+                    return Err(invalid("unbalanced parentheses"));
+                }
+            }
+            (None, ';') => return Err(invalid("';' is not allowed")),
+            (None, '-') if chars.peek() == Some(&'-') => {
+                return Err(invalid("comments are not allowed"))
+            }
+            (None, '/') if chars.peek() == Some(&'*') => {
+                return Err(invalid("comments are not allowed"))
+            }
+            _ => {}
+        }
+    }
+    if quote.is_some() {
+        return Err(invalid("unterminated quote"));
+    }
+    if depth != 0 {
+        return Err(invalid("unbalanced parentheses"));
+    }
+
+    let defs = split_top_level(columns);
+    if defs.is_empty() {
+        return Err(invalid("no columns"));
+    }
+    for def in defs {
+        let mut words = def.split_whitespace();
+        let first = words.next().unwrap_or_default();
+        if TABLE_CONSTRAINTS.contains(&first.to_uppercase().as_str()) {
+            continue;
+        }
+        let name = first.trim_matches(|c| matches!(c, '"' | '`' | '[' | ']'));
+        if name.is_empty() || !is_valid_identifier(name) {
+            return Err(anyhow::anyhow!("Invalid column name: {}", first));
+        }
+        let Some(next) = words.next() else {
+            continue;
+        };
+        let word = next.split('(').next().unwrap_or_default().to_uppercase();
+        if !COLUMN_TYPES.contains(&word.as_str()) && !COLUMN_CONSTRAINTS.contains(&word.as_str()) {
+            if strict_types {
+                return Err(anyhow::anyhow!(
+                    "Unknown type for column '{}': {}",
+                    name,
+                    next
+                ));
+            }
+            if !is_valid_identifier(&word) {
+                return Err(anyhow::anyhow!(
+                    "Invalid type for column '{}': {}",
+                    name,
+                    next
+                ));
+            }
+            println!("⚠️ Column '{}' has an unknown type: {}", name, next);
+        }
+    }
+    Ok(())
+}
+
+/// Splits a definition list on commas that aren't nested inside parentheses.
+fn split_top_level(list: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in list.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(list[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(list[start..].trim());
+    parts.retain(|p| !p.is_empty());
+    parts
+}
+
+/// Applies the connection settings (PRAGMAs) to a freshly opened connection.
+fn apply_config(conn: &Connection, config: &DbConfig) -> rusqlite::Result<()> {
+    conn.busy_timeout(Duration::from_millis(config.busy_timeout_ms))?;
+    conn.pragma_update(None, "foreign_keys", config.foreign_keys)?;
+    conn.pragma_update(None, "secure_delete", config.secure_delete)?;
+    if config.wal {
+        // journal_mode returns the resulting mode as a row
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+    }
+    Ok(())
+}
+
+// --- METRICS ---
+
+/// Route of the Prometheus endpoint (see `with_metrics`)
+#[cfg(feature = "metrics")]
+const METRICS_PATH: &str = "/metrics";
+
+/// Upper bounds, in seconds, of the request latency histogram buckets
+#[cfg(feature = "metrics")]
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Request counts and latencies, labelled by table (`none` for other
+/// routes) and method
+#[cfg(feature = "metrics")]
+#[derive(Default)]
+struct Metrics {
+    inner: Mutex<MetricsData>,
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Default)]
+struct MetricsData {
+    /// Requests by (table, method, status)
+    requests: std::collections::BTreeMap<(String, String, u16), u64>,
+    /// Latencies by (table, method)
+    latencies: std::collections::BTreeMap<(String, String), Histogram>,
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Default)]
+struct Histogram {
+    /// Cumulative: each bucket counts every request at or below its bound
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+#[cfg(feature = "metrics")]
+impl Metrics {
+    fn record(&self, table: &str, method: &str, status: u16, elapsed: Duration) {
+        let mut data = self
+            .inner
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let key = (table.to_string(), method.to_string());
+        *data
+            .requests
+            .entry((key.0.clone(), key.1.clone(), status))
+            .or_default() += 1;
+
+        let secs = elapsed.as_secs_f64();
+        let histogram = data.latencies.entry(key).or_default();
+        for (bucket, bound) in histogram.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if secs <= bound {
+                *bucket += 1;
+            }
+        }
+        histogram.sum += secs;
+        histogram.count += 1;
+    }
+
+    /// The Prometheus text exposition format
+    fn render(&self) -> String {
+        use std::fmt::Write;
+
+        let data = self
+            .inner
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let mut out = String::new();
+        out.push_str("# HELP easydb_requests_total HTTP requests served.\n");
+        out.push_str("# TYPE easydb_requests_total counter\n");
+        for ((table, method, status), count) in &data.requests {
+            let _ = writeln!(
+                out,
+                "easydb_requests_total{{table=\"{}\",method=\"{}\",status=\"{}\"}} {}",
+                table, method, status, count
+            );
+        }
+
+        out.push_str("# HELP easydb_request_duration_seconds HTTP request latency.\n");
+        out.push_str("# TYPE easydb_request_duration_seconds histogram\n");
+        for ((table, method), histogram) in &data.latencies {
+            let labels = format!("table=\"{}\",method=\"{}\"", table, method);
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                let _ = writeln!(
+                    out,
+                    "easydb_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "easydb_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "easydb_request_duration_seconds_sum{{{}}} {}",
+                labels, histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "easydb_request_duration_seconds_count{{{}}} {}",
+                labels, histogram.count
+            );
+        }
+        out
+    }
+}
+
+/// Middleware: counts and times every request. Only exposed tables become
+/// labels, so unknown paths can't grow the series without bound.
+#[cfg(feature = "metrics")]
+async fn record_metrics(
+    metrics: Arc<Metrics>,
+    tables: Arc<HashSet<String>>,
+    prefix: Arc<str>,
+    req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let table = req
+        .uri()
+        .path()
+        .strip_prefix(&*prefix)
+        .and_then(|path| path.trim_start_matches('/').split('/').next())
+        .filter(|segment| tables.contains(*segment))
+        .unwrap_or("none")
+        .to_string();
+    let method = req.method().to_string();
+
+    let start = std::time::Instant::now();
+    let response = next.run(req).await;
+    metrics.record(&table, &method, response.status().as_u16(), start.elapsed());
+    response
+}
+
+/// GET /metrics: The collected metrics for a Prometheus scraper
+#[cfg(feature = "metrics")]
+async fn handle_metrics(metrics: Arc<Metrics>) -> impl axum::response::IntoResponse {
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        metrics.render(),
+    )
+}
+
+// --- SHUTDOWN HELPERS ---
+// Connections are served on their own tasks, so dropping the server future is
+// not enough to close them. The listener hands out streams that fail all I/O
+// once the drain deadline is signalled, which makes those tasks finish.
+
+struct DrainListener {
+    inner: tokio::net::TcpListener,
+    closed: watch::Receiver<bool>,
+}
+
+impl axum::serve::Listener for DrainListener {
+    type Io = DrainStream;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        let (io, addr) = axum::serve::Listener::accept(&mut self.inner).await;
+        let mut closed = self.closed.clone();
+        let stream = DrainStream {
+            inner: io,
+            closed: Box::pin(async move {
+                let _ = closed.wait_for(|c| *c).await;
+            }),
+            is_closed: false,
+        };
+        (stream, addr)
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        self.inner.local_addr()
+    }
+}
+
+struct DrainStream {
+    inner: tokio::net::TcpStream,
+    closed: Pin<Box<dyn Future<Output = ()> + Send>>,
+    is_closed: bool,
+}
+
+impl DrainStream {
+    fn poll_closed(&mut self, cx: &mut Context<'_>) -> bool {
+        if !self.is_closed && self.closed.as_mut().poll(cx).is_ready() {
+            self.is_closed = true;
+        }
+        self.is_closed
+    }
+}
+
+fn drain_error() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::ConnectionAborted,
+        "connection closed after drain timeout",
+    )
+}
+
+impl AsyncRead for DrainStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        if self.poll_closed(cx) {
+            return Poll::Ready(Err(drain_error()));
+        }
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for DrainStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        if self.poll_closed(cx) {
+            return Poll::Ready(Err(drain_error()));
+        }
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        if self.poll_closed(cx) {
+            return Poll::Ready(Err(drain_error()));
+        }
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+// =========================================================
+// 2. HANDLERS (API Logic)
+// =========================================================
+
+/// Extractor: A JSON request body, like `Json<Value>`, except that a missing
+/// content type or malformed JSON is answered as `{"error": ...}` like every
+/// other error (keeping axum's status: 400, 415 or 422)
+struct JsonBody(Value);
+
+impl<S: Send + Sync> axum::extract::FromRequest<S> for JsonBody {
+    type Rejection = (StatusCode, Json<Value>);
+
+    async fn from_request(req: axum::extract::Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<Value>::from_request(req, state).await {
+            Ok(Json(value)) => Ok(JsonBody(value)),
+            Err(rejection) => Err((
+                rejection.status(),
+                Json(serde_json::json!({"error": rejection.body_text()})),
+            )),
+        }
+    }
+}
+
+/// Extractor: The query string as a map, like `Query<HashMap<String, String>>`,
+/// except that a filter key given more than once (`?grade=10-A&grade=11-B`)
+/// keeps all of its values in `repeated` instead of only the last one
+#[derive(Clone, Default)]
+struct QueryParams {
+    values: HashMap<String, String>,
+    repeated: HashMap<String, Vec<String>>,
+}
+
+impl std::ops::Deref for QueryParams {
+    type Target = HashMap<String, String>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.values
+    }
+}
+
+impl std::ops::DerefMut for QueryParams {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.values
+    }
+}
+
+impl<S: Send + Sync> axum::extract::FromRequestParts<S> for QueryParams {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let mut params = QueryParams::default();
+        let query = parts.uri.query().unwrap_or("");
+        for (k, v) in form_urlencoded::parse(query.as_bytes()) {
+            if !k.starts_with('_') {
+                params
+                    .repeated
+                    .entry(k.to_string())
+                    .or_default()
+                    .push(v.to_string());
+            }
+            params.values.insert(k.into_owned(), v.into_owned());
+        }
+        params.repeated.retain(|_, values| values.len() > 1);
+        Ok(params)
+    }
+}
+
+/// GET /_health: Checks that every database connection answers a trivial query
+async fn handle_health(conns: Arc<Vec<Arc<Mutex<Connection>>>>) -> (StatusCode, Json<Value>) {
+    blocking(move || {
+        for conn in conns.iter() {
+            let reachable = lock_conn(conn)
+                .query_row("SELECT 1", [], |_| Ok(()))
+                .is_ok();
+            if !reachable {
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(serde_json::json!({"status": "unavailable"})),
+                );
+            }
+        }
+        (StatusCode::OK, Json(serde_json::json!({"status": "ok"})))
+    })
+    .await
+}
+
+/// Answers `OPTIONS` on a table route with the methods registered for it.
+/// CORS preflights never reach this; the CORS layer answers them first.
+async fn handle_options(allow: &'static str) -> impl axum::response::IntoResponse {
+    (StatusCode::NO_CONTENT, [(axum::http::header::ALLOW, allow)])
+}
+
+/// Fallback for write routes when the database is opened read-only
+async fn handle_read_only() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::METHOD_NOT_ALLOWED,
+        Json(serde_json::json!({"error": "Database is read-only"})),
+    )
+}
+
+/// Introspection routes while `with_introspection(false)` is set
+async fn handle_hidden() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(serde_json::json!({"error": "Not found"})),
+    )
+}
+
+/// Fallback for paths no route matches. A mistyped table name is answered
+/// with the list of exposed tables.
+async fn handle_not_found(
+    tables: Arc<Vec<String>>,
+    introspection: bool,
+    uri: axum::http::Uri,
+) -> (StatusCode, Json<Value>) {
+    let first = uri.path().trim_start_matches('/').split('/').next();
+    let body = match first {
+        _ if !introspection => serde_json::json!({"error": "Not found"}),
+        Some(t) if tables.iter().any(|known| known == t) => {
+            serde_json::json!({"error": "Not found"})
+        }
+        _ => serde_json::json!({"error": "unknown table", "available": *tables}),
+    };
+    (StatusCode::NOT_FOUND, Json(body))
+}
+
+/// GET /{table}: Dispatches to `handle_get`, or streams the rows with `?_stream=true`.
+/// Paginated listings also get `X-Total-Count` and `Link` headers.
+async fn handle_list(
+    State(table): State<Arc<TableState>>,
+    scopes: Scopes,
+    axum::extract::OriginalUri(uri): axum::extract::OriginalUri,
+    request_headers: axum::http::HeaderMap,
+    params: QueryParams,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    let single = params.get("_single").is_some_and(|v| v == "true");
+    if params.get("_stream").is_some_and(|v| v == "true") {
+        if single {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "_single can't be streamed"})),
+            )
+                .into_response();
+        }
+        return stream_rows(table, scopes, params).await;
+    }
+
+    let (status, body) = handle_get(State(Arc::clone(&table)), scopes, params.clone()).await;
+    if status != StatusCode::OK {
+        return (status, body).into_response();
+    }
+    if single {
+        return match single_row(body.0) {
+            (StatusCode::OK, row) => {
+                with_etag(&request_headers, axum::http::HeaderMap::new(), row.0)
+            }
+            other => other.into_response(),
+        };
+    }
+    match blocking(move || pagination_headers(&table, &params, uri.path())).await {
+        Ok(headers) => with_etag(&request_headers, headers, body.0),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Helper: Answers a successful read with a weak `ETag` (a hash of the JSON
+/// body) next to `headers`, or with a bodiless `304 Not Modified` when the
+/// request's `If-None-Match` already names it, so polling clients only download changes
+fn with_etag(
+    request_headers: &axum::http::HeaderMap,
+    mut headers: axum::http::HeaderMap,
+    body: Value,
+) -> axum::response::Response {
+    use axum::http::header;
+    use axum::response::IntoResponse;
+    use std::hash::{Hash, Hasher};
+
+    let json = body.to_string();
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    json.hash(&mut hasher);
+    let etag = format!("W/\"{:016x}\"", hasher.finish());
+
+    // Weak comparison: `W/` prefixes don't matter
+    let unchanged = request_headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag.trim_start_matches("W/"));
+    if let Ok(value) = etag.parse() {
+        headers.insert(header::ETAG, value);
+    }
+    if unchanged {
+        return (StatusCode::NOT_MODIFIED, headers).into_response();
+    }
+    headers.insert(
+        header::CONTENT_TYPE,
+        axum::http::HeaderValue::from_static("application/json"),
+    );
+    (StatusCode::OK, headers, json).into_response()
+}
+
+/// Helper: Unwraps a one-row listing for `_single=true`: `404` when nothing
+/// matched, `409` when more than one row did.
+fn single_row(rows: Value) -> (StatusCode, Json<Value>) {
+    let mut rows = match rows {
+        Value::Array(rows) => rows,
+        other => return (StatusCode::OK, Json(other)),
+    };
+    match rows.len() {
+        1 => (StatusCode::OK, Json(rows.remove(0))),
+        0 => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "No matching record"})),
+        ),
+        n => (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({"error": "More than one record matches", "count": n})),
+        ),
+    }
+}
+
+/// Helper: `X-Total-Count` (rows matching the filters, ignoring pagination)
+/// and `Link` headers with first/prev/next/last pages, json-server style.
+/// Empty for unpaginated requests.
+fn pagination_headers(
+    table: &TableState,
+    params: &QueryParams,
+    path: &str,
+) -> Result<axum::http::HeaderMap, (StatusCode, Json<Value>)> {
+    let mut headers = axum::http::HeaderMap::new();
+    let Ok(Some((limit, offset))) = pagination(params) else {
+        return Ok(headers);
+    };
+
+    let mut unpaged = params.clone();
+    for key in ["_limit", "_offset", "_page", "_per_page"] {
+        unpaged.remove(key);
+    }
+    let (sql, sql_params) = select_query(table, &unpaged)?;
+    let total: i64 = lock_conn(&table.conn)
+        .query_row(
+            &format!("SELECT COUNT(*) FROM ({})", sql),
+            rusqlite::params_from_iter(sql_params.iter().map(|p| p.as_ref())),
+            |row| row.get(0),
+        )
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+        })?;
+    headers.insert("x-total-count", total.into());
+    if limit <= 0 {
+        return Ok(headers);
+    }
+
+    // Links keep the style of the request: `_page` numbers or `_offset`s
+    let by_page = !params.contains_key("_limit") && !params.contains_key("_offset");
+    let link = |target: i64, rel: &str| {
+        let mut query: Vec<(String, String)> = params
+            .iter()
+            .filter(|(k, _)| *k != "_page" && *k != "_offset")
+            .flat_map(|(k, v)| match params.repeated.get(k) {
+                Some(values) => values.iter().map(|v| (k.clone(), v.clone())).collect(),
+                None => vec![(k.clone(), v.clone())],
+            })
+            .collect();
+        if by_page {
+            query.push(("_page".to_string(), (target / limit + 1).to_string()));
+        } else {
+            query.push(("_offset".to_string(), target.to_string()));
+        }
+        query.sort();
+        let query = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(query)
+            .finish();
+        format!("<{}?{}>; rel=\"{}\"", path, query, rel)
+    };
+
+    let last = if total > 0 {
+        (total - 1) / limit * limit
+    } else {
+        0
+    };
+    let mut links = vec![link(0, "first")];
+    if offset > 0 {
+        links.push(link((offset - limit).max(0), "prev"));
+    }
+    if offset + limit < total {
+        links.push(link(offset + limit, "next"));
+    }
+    links.push(link(last, "last"));
+    if let Ok(value) = links.join(", ").parse() {
+        headers.insert(axum::http::header::LINK, value);
+    }
+    Ok(headers)
+}
+
+/// Rows serialized per chunk of a streamed listing
+const STREAM_CHUNK_ROWS: usize = 100;
+
+/// GET /{table}?_stream=true: Writes the JSON array a chunk at a time, so
+/// memory stays bounded however many rows match. The query runs on a
+/// blocking thread that holds the table's connection until the last row is
+/// sent; errors found before the first row still get a normal error response.
+async fn stream_rows(
+    table: Arc<TableState>,
+    scopes: Scopes,
+    params: QueryParams,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    if params.contains_key("_expand") || params.contains_key("_embed") {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "_stream can't be combined with _expand or _embed"})),
+        )
+            .into_response();
+    }
+
+    let typed = wants_typed(&params);
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
+    let (chunk_tx, chunk_rx) = mpsc::channel::<Result<Vec<u8>, std::io::Error>>(8);
+    tokio::task::spawn_blocking(move || {
+        let internal = |e: rusqlite::Error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+        };
+        let conn = lock_conn(&table.conn);
+        let prepared = select_query(&table, &params).and_then(|(sql, sql_params)| {
+            check_scan_guard(&conn, &table, &sql, &sql_params)?;
+            let stmt = conn.prepare(&sql).map_err(internal)?;
+            Ok((stmt, sql_params))
+        });
+        let (mut stmt, sql_params) = match prepared {
+            Ok(p) => p,
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+        let mut rows = match stmt.query(rusqlite::params_from_iter(
+            sql_params.iter().map(|p| p.as_ref()),
+        )) {
+            Ok(rows) => rows,
+            Err(e) => {
+                let _ = ready_tx.send(Err(internal(e)));
+                return;
+            }
+        };
+        if ready_tx.send(Ok(())).is_err() {
+            return;
+        }
+
+        let mut chunk = b"[".to_vec();
+        let mut count = 0;
+        loop {
+            match rows.next() {
+                Ok(Some(row)) => {
+                    if count > 0 {
+                        chunk.push(b',');
+                    }
+                    let value = if typed {
+                        row_to_typed_json(row, &table.config, &scopes)
+                    } else {
+                        row_to_json(row, &table.config, &scopes)
+                    };
+                    serde_json::to_writer(&mut chunk, &value).unwrap_or_default();
+                    count += 1;
+                    if count % STREAM_CHUNK_ROWS == 0
+                        && chunk_tx
+                            .blocking_send(Ok(std::mem::take(&mut chunk)))
+                            .is_err()
+                    {
+                        return; // The client went away
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    // Too late for an error status; cut the body short instead
+                    tracing::error!("streaming {} failed: {}", table.name, e);
+                    let _ = chunk_tx.blocking_send(Err(std::io::Error::other(e)));
+                    return;
+                }
+            }
+        }
+        chunk.push(b']');
+        let _ = chunk_tx.blocking_send(Ok(chunk));
+    });
+
+    match ready_rx.await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => return e.into_response(),
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": "Query task failed"})),
+            )
+                .into_response()
+        }
+    }
+    let chunks = futures_util::stream::unfold(chunk_rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+    (
+        [(axum::http::header::CONTENT_TYPE, "application/json")],
+        axum::body::Body::from_stream(chunks),
+    )
+        .into_response()
+}
+
+/// GET: List, filter, and sort data (SECURE VERSION)
+async fn handle_get(
+    State(table): State<Arc<TableState>>,
+    scopes: Scopes,
+    params: QueryParams,
+) -> (StatusCode, Json<Value>) {
+    blocking(move || {
+        let conn = lock_conn(&table.conn);
+
+        // Relations to expand/embed, validated before any work is done
+        let (expand, embed) = match requested_relations(&table, &params) {
+            Ok(r) => r,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": e})),
+                )
+            }
+        };
+
+        let (sql, sql_params) = match select_query(&table, &params) {
+            Ok(q) => q,
+            Err(e) => return e,
+        };
+        if let Err(e) = check_scan_guard(&conn, &table, &sql, &sql_params) {
+            return e;
+        }
+
+        // 4. Execute Query
+        let mut results: Vec<Value> = {
+            let mut stmt = match conn.prepare(&sql) {
+                Ok(s) => s,
+                Err(e) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    )
+                }
+            };
+
+            let typed = wants_typed(&params);
+            let rows = stmt.query_map(
+                rusqlite::params_from_iter(sql_params.iter().map(|p| p.as_ref())),
+                |row| {
+                    Ok(if typed {
+                        row_to_typed_json(row, &table.config, &scopes)
+                    } else {
+                        row_to_json(row, &table.config, &scopes)
+                    })
+                },
+            );
+
+            // A row that fails to load fails the request instead of going missing
+            match rows.and_then(|mapped| mapped.collect::<rusqlite::Result<Vec<Value>>>()) {
+                Ok(rows) => rows,
+                Err(e) => {
+                    tracing::error!("listing {} failed: {}", table.name, e);
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    );
+                }
+            }
+        };
+
+        // 5. Expand parents / embed children (the lock is released first; a
+        //    related table may share it)
+        drop(conn);
+        if let Err(e) = attach_relations(&expand, &embed, &mut results, &scopes) {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": e.to_string()})),
+            );
+        }
+
+        (StatusCode::OK, Json(Value::from(results)))
+    })
+    .await
+}
+
+/// How a list filter compares its column, chosen by the query key's suffix
+#[derive(Clone, Copy, PartialEq)]
+enum FilterOp {
+    /// `col=value`
+    Eq,
+    /// `col_ci=value`: equality ignoring ASCII case (`COLLATE NOCASE`)
+    EqCi,
+    /// `col__in=1,2,3` matches any of the comma-separated values
+    In,
+    /// `col_is_null=true`
+    IsNull,
+    /// `col_not_null=true`
+    NotNull,
+    /// `col_ne=value`
+    Ne,
+    /// `col_lt=value`
+    Lt,
+    /// `col_lte=value`
+    Lte,
+    /// `col_gt=value`
+    Gt,
+    /// `col_gte=value`
+    Gte,
+}
+
+impl FilterOp {
+    /// Splits a query key into the column name and its operator. A key that
+    /// is itself one of the table's columns is always an equality filter.
+    fn parse<'a>(key: &'a str, columns: &HashSet<String>) -> (&'a str, FilterOp) {
+        if columns.contains(key) {
+            return (key, FilterOp::Eq);
+        }
+        let suffixes = [
+            ("__in", FilterOp::In),
+            ("_is_null", FilterOp::IsNull),
+            ("_not_null", FilterOp::NotNull),
+            ("_ne", FilterOp::Ne),
+            ("_lt", FilterOp::Lt),
+            ("_lte", FilterOp::Lte),
+            ("_gt", FilterOp::Gt),
+            ("_gte", FilterOp::Gte),
+            ("_ci", FilterOp::EqCi),
+        ];
+        for (suffix, op) in suffixes {
+            if let Some(col) = key.strip_suffix(suffix) {
+                return (col, op);
+            }
+        }
+        (key, FilterOp::Eq)
+    }
+
+    /// SQL operator of the single-value comparisons
+    fn comparison(self) -> Option<&'static str> {
+        match self {
+            FilterOp::Eq | FilterOp::EqCi => Some("="),
+            FilterOp::Ne => Some("<>"),
+            FilterOp::Lt => Some("<"),
+            FilterOp::Lte => Some("<="),
+            FilterOp::Gt => Some(">"),
+            FilterOp::Gte => Some(">="),
+            FilterOp::In | FilterOp::IsNull | FilterOp::NotNull => None,
+        }
+    }
+
+    /// Collation appended to a comparison's placeholder
+    fn collation(self) -> &'static str {
+        if self == FilterOp::EqCi {
+            " COLLATE NOCASE"
+        } else {
+            ""
+        }
+    }
+}
+
+/// Bind parameters of a dynamically built query
+type SqlParams = Vec<Box<dyn ToSql>>;
+
+/// Helper: Checks that a filter names a column the caller may query
+fn filter_column(table: &TableState, column: &str) -> Result<(), (StatusCode, Json<Value>)> {
+    if !is_valid_identifier(column) || is_hidden_for_queries(table, column) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Invalid column name"})),
+        ));
+    }
+    known_column(table, column)
+}
+
+/// Helper: Turns a `_where` tree into a parenthesized, parameterized condition.
+/// A node is either `{"or": [nodes]}` / `{"and": [nodes]}`, or an object of
+/// filters (same keys as the query string) that must all match.
+fn where_clause(
+    table: &TableState,
+    node: &Value,
+    sql_params: &mut SqlParams,
+) -> Result<String, (StatusCode, Json<Value>)> {
+    let bad = |msg: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": format!("Invalid _where: {}", msg)})),
+        )
+    };
+    let obj = match node {
+        Value::Object(obj) if !obj.is_empty() => obj,
+        _ => return Err(bad("expected a non-empty object".to_string())),
+    };
+
+    if obj.len() == 1 {
+        if let Some((group, joiner)) = [("or", " OR "), ("and", " AND ")]
+            .into_iter()
+            .find(|(group, _)| obj.contains_key(*group))
+        {
+            let children = match &obj[group] {
+                Value::Array(children) if !children.is_empty() => children,
+                _ => return Err(bad(format!("'{}' expects a non-empty array", group))),
+            };
+            let clauses = children
+                .iter()
+                .map(|child| where_clause(table, child, sql_params))
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(format!("({})", clauses.join(joiner)));
+        }
+    }
+
+    let mut clauses = Vec::new();
+    for (key, value) in obj {
+        let (col, op) = FilterOp::parse(key, &table.columns);
+        filter_column(table, col)?;
+        let clause = match (op, value) {
+            (FilterOp::In, Value::Array(values)) if !values.is_empty() => {
+                if values.iter().any(|v| v.is_array() || v.is_object()) {
+                    return Err(bad(format!("'{}' expects an array of values", key)));
+                }
+                sql_params.extend(
+                    values
+                        .iter()
+                        .map(|v| Box::new(json_to_sql(v)) as Box<dyn ToSql>),
+                );
+                format!(
+                    "{} IN ({})",
+                    quote_ident(col),
+                    vec!["?"; values.len()].join(", ")
+                )
+            }
+            (FilterOp::IsNull | FilterOp::NotNull, Value::Bool(b)) => {
+                let want_null = *b == (op == FilterOp::IsNull);
+                let test = if want_null { "IS NULL" } else { "IS NOT NULL" };
+                format!("{} {}", quote_ident(col), test)
+            }
+            (_, Value::String(_) | Value::Number(_) | Value::Bool(_))
+                if op.comparison().is_some() =>
+            {
+                match value {
+                    Value::String(text) => {
+                        sql_params.push(Box::new(filter_value(table, col, text)?))
+                    }
+                    other => sql_params.push(Box::new(json_to_sql(other))),
+                }
+                format!(
+                    "{} {} ?{}",
+                    quote_ident(col),
+                    op.comparison().unwrap_or("="),
+                    op.collation()
+                )
+            }
+            _ => return Err(bad(format!("unexpected value for '{}'", key))),
+        };
+        clauses.push(clause);
+    }
+    Ok(format!("({})", clauses.join(" AND ")))
+}
+
+/// Helper: The WHERE conditions of a request: column filters, `_where` and
+/// the soft-delete check
+fn filter_conditions(
+    table: &TableState,
+    params: &QueryParams,
+) -> Result<(Vec<String>, SqlParams), (StatusCode, Json<Value>)> {
+    let mut filters = Vec::new();
+    let mut sql_params: SqlParams = Vec::new();
+
+    for (k, v) in params.iter() {
+        if k.starts_with('_') {
+            continue;
+        }
+        let (col, op) = FilterOp::parse(k, &table.columns);
+        filter_column(table, col)?;
+        // A repeated key matches any of its values: `IN (...)` for plain
+        // equality, the conditions OR'd together otherwise
+        match params.repeated.get(k) {
+            Some(values) if op == FilterOp::Eq => {
+                let placeholders = vec!["?"; values.len()].join(", ");
+                filters.push(format!("{} IN ({})", quote_ident(col), placeholders));
+                for value in values {
+                    sql_params.push(Box::new(filter_value(table, col, value)?));
+                }
+            }
+            Some(values) => {
+                let conditions = values
+                    .iter()
+                    .map(|value| filter_condition(table, k, col, op, value, &mut sql_params))
+                    .collect::<Result<Vec<_>, _>>()?;
+                filters.push(format!("({})", conditions.join(" OR ")));
+            }
+            None => filters.push(filter_condition(table, k, col, op, v, &mut sql_params)?),
+        }
+    }
+
+    if let Some(term) = params.get("_q").filter(|t| !t.is_empty()) {
+        filters.push(search_condition(table, term, &mut sql_params));
+    }
+
+    if let Some(tree) = params.get("_where") {
+        let tree: Value = serde_json::from_str(tree).map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": format!("Invalid _where JSON: {}", e)})),
+            )
+        })?;
+        filters.push(where_clause(table, &tree, &mut sql_params)?);
+    }
+
+    if hides_deleted(table, params) {
+        filters.push("deleted_at IS NULL".to_string());
+    }
+    Ok((filters, sql_params))
+}
+
+/// Helper: `_q=term`: a substring match of `term` (case-insensitive for ASCII,
+/// `%` and `_` taken literally) against any of the table's text columns
+fn search_condition(table: &TableState, term: &str, sql_params: &mut SqlParams) -> String {
+    if table.text_columns.is_empty() {
+        return "0".to_string();
+    }
+    let pattern = format!(
+        "%{}%",
+        term.replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+    let conditions: Vec<String> = table
+        .text_columns
+        .iter()
+        .map(|col| {
+            sql_params.push(Box::new(pattern.clone()));
+            format!("{} LIKE ? ESCAPE '\\'", quote_ident(col))
+        })
+        .collect();
+    format!("({})", conditions.join(" OR "))
+}
+
+/// Helper: The SQL condition for one `column[__op]=value` filter, binding its values
+fn filter_condition(
+    table: &TableState,
+    k: &str,
+    col: &str,
+    op: FilterOp,
+    v: &str,
+    sql_params: &mut SqlParams,
+) -> Result<String, (StatusCode, Json<Value>)> {
+    match op {
+        FilterOp::Eq
+        | FilterOp::EqCi
+        | FilterOp::Ne
+        | FilterOp::Lt
+        | FilterOp::Lte
+        | FilterOp::Gt
+        | FilterOp::Gte => {
+            sql_params.push(Box::new(filter_value(table, col, v)?));
+            Ok(format!(
+                "{} {} ?{}",
+                quote_ident(col),
+                op.comparison().unwrap_or("="),
+                op.collation()
+            ))
+        }
+        FilterOp::In => {
+            let values: Vec<&str> = v.split(',').map(|v| v.trim()).collect();
+            let placeholders = vec!["?"; values.len()].join(", ");
+            for value in values {
+                sql_params.push(Box::new(filter_value(table, col, value)?));
+            }
+            Ok(format!("{} IN ({})", quote_ident(col), placeholders))
+        }
+        FilterOp::IsNull | FilterOp::NotNull => {
+            let want_null = match v {
+                "true" => op == FilterOp::IsNull,
+                "false" => op == FilterOp::NotNull,
+                _ => {
+                    return Err((
+                        StatusCode::BAD_REQUEST,
+                        Json(
+                            serde_json::json!({"error": format!("Invalid value for {}: expected true or false", k)}),
+                        ),
+                    ))
+                }
+            };
+            let test = if want_null { "IS NULL" } else { "IS NOT NULL" };
+            Ok(format!("{} {}", quote_ident(col), test))
+        }
+    }
+}
+
+/// Helper: A filter value as bound for its column: `true`/`false` become 1/0
+/// for `ColumnKind::Bool` columns, and `ColumnKind::DateTime` values are
+/// normalized like stored ones (a plain date means midnight UTC).
+fn filter_value(
+    table: &TableState,
+    column: &str,
+    value: &str,
+) -> Result<String, (StatusCode, Json<Value>)> {
+    match (table.config.kinds.get(column), value) {
+        (Some(ColumnKind::Bool), "true") => Ok("1".to_string()),
+        (Some(ColumnKind::Bool), "false") => Ok("0".to_string()),
+        (Some(ColumnKind::DateTime), _) => normalize_rfc3339(value)
+            .or_else(|| {
+                let date = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+                normalize_rfc3339(&format!("{}T00:00:00Z", date))
+            })
+            .ok_or_else(|| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({
+                        "error": format!("Invalid date for {}: {}", column, value)
+                    })),
+                )
+            }),
+        _ => Ok(value.to_string()),
+    }
+}
+
+/// Helper: Whether the request filters rows at all (column filters, `_q` or `_where`)
+fn has_filters(params: &HashMap<String, String>) -> bool {
+    params
+        .iter()
+        .any(|(k, v)| !k.starts_with('_') || k == "_where" || (k == "_q" && !v.is_empty()))
+}
+
+/// Helper: Builds the SELECT for a list request from its filter, sort and pagination params
+fn select_query(
+    table: &TableState,
+    params: &QueryParams,
+) -> Result<(String, SqlParams), (StatusCode, Json<Value>)> {
+    // 0. Projection: `_fields=a,b` picks columns, `_distinct=true` drops duplicates
+    let fields = match params.get("_fields") {
+        Some(list) => {
+            let fields: Vec<&str> = list.split(',').map(|f| f.trim()).collect();
+            for field in &fields {
+                if table.config.hidden_columns.contains(*field) {
+                    return Err((
+                        StatusCode::BAD_REQUEST,
+                        Json(serde_json::json!({"error": "Invalid column name"})),
+                    ));
+                }
+                filter_column(table, field)?;
+            }
+            quoted_list(&fields)
+        }
+        None => "*".to_string(),
+    };
+    let distinct = if params.get("_distinct").is_some_and(|v| v == "true") {
+        "DISTINCT "
+    } else {
+        ""
+    };
+    let mut sql = format!(
+        "SELECT {}{} FROM {}",
+        distinct,
+        fields,
+        quote_ident(&table.name)
+    );
+
+    // 1. Secure Filtering (Parameterized Query)
+    let (filters, mut sql_params) = filter_conditions(table, params)?;
+    if !filters.is_empty() {
+        sql.push_str(&format!(" WHERE {}", filters.join(" AND ")));
+    }
+
+    // 2. Sorting: `_sort`, else the table's `default_sort`
+    let sort = match params.get("_sort") {
+        Some(sort_col) => {
+            if !is_valid_identifier(sort_col) || is_hidden_for_queries(table, sort_col) {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": "Invalid sort column"})),
+                ));
+            }
+            known_column(table, sort_col)?;
+            Some((sort_col.as_str(), false))
+        }
+        None => table
+            .config
+            .default_sort
+            .as_ref()
+            .map(|(column, descending)| (column.as_str(), *descending)),
+    };
+    if let Some((sort_col, descending)) = sort {
+        let descending = match params.get("_order") {
+            Some(order) => order.to_uppercase() == "DESC",
+            None => descending,
+        };
+        let safe_order = if descending { "DESC" } else { "ASC" };
+        let nulls = match params.get("_nulls").map(|s| s.as_str()) {
+            None => "",
+            Some("first") => " NULLS FIRST",
+            Some("last") => " NULLS LAST",
+            Some(_) => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": "_nulls must be first or last"})),
+                ))
+            }
+        };
+        sql.push_str(&format!(
+            " ORDER BY {} {}{}",
+            quote_ident(sort_col),
+            safe_order,
+            nulls
+        ));
+    }
+
+    // 3. Pagination
+    match pagination(params) {
+        Ok(Some((limit, offset))) => {
+            sql.push_str(" LIMIT ? OFFSET ?");
+            sql_params.push(Box::new(limit));
+            sql_params.push(Box::new(offset));
+        }
+        Ok(None) => {}
+        Err(e) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": e})),
+            ))
+        }
+    }
+
+    Ok((sql, sql_params))
+}
+
+/// GET /{table}.csv: The rows selected by the usual filter/sort/pagination params, as CSV
+async fn handle_export_csv(
+    State(table): State<Arc<TableState>>,
+    scopes: Scopes,
+    params: QueryParams,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    blocking(move || {
+        let (sql, sql_params) = match select_query(&table, &params) {
+            Ok(q) => q,
+            Err(e) => return e.into_response(),
+        };
+
+        let conn = lock_conn(&table.conn);
+        if let Err(e) = check_scan_guard(&conn, &table, &sql, &sql_params) {
+            return e.into_response();
+        }
+        match export_csv(&conn, &table, &scopes, &sql, &sql_params) {
+            Ok(body) => (
+                [
+                    (
+                        axum::http::header::CONTENT_TYPE,
+                        "text/csv; charset=utf-8".to_string(),
+                    ),
+                    (
+                        axum::http::header::CONTENT_DISPOSITION,
+                        format!("attachment; filename=\"{}.csv\"", table.name),
+                    ),
+                ],
+                body,
+            )
+                .into_response(),
+            Err(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+                .into_response(),
+        }
+    })
+    .await
+}
+
+/// Helper: The table's column names and declared types, in schema order
+/// (empty if it doesn't exist)
+fn schema_columns(conn: &Connection, table_name: &str) -> rusqlite::Result<Vec<(String, String)>> {
+    conn.prepare(&format!("PRAGMA table_info({})", quote_ident(table_name)))?
+        .query_map([], |row| Ok((row.get(1)?, row.get(2)?)))?
+        .collect()
+}
+
+/// Helper: The table's `INTEGER PRIMARY KEY` column, which aliases its rowid
+/// (`None` for other keys and for `WITHOUT ROWID` tables)
+fn rowid_alias(conn: &Connection, table_name: &str) -> rusqlite::Result<Option<String>> {
+    let without_rowid: bool = conn
+        .query_row(
+            "SELECT wr FROM pragma_table_list WHERE schema = 'main' AND name = ?",
+            [table_name],
+            |row| row.get(0),
+        )
+        .unwrap_or(false);
+    if without_rowid {
+        return Ok(None);
+    }
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", quote_ident(table_name)))?;
+    let keys = stmt
+        .query_map([], |row| {
+            let (name, decl, pk): (String, String, i64) = (row.get(1)?, row.get(2)?, row.get(5)?);
+            Ok((pk > 0).then_some((name, decl)))
+        })?
+        .filter_map(|row| row.transpose())
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(match keys.as_slice() {
+        [(name, decl)] if decl.eq_ignore_ascii_case("INTEGER") => Some(name.clone()),
+        _ => None,
+    })
+}
+
+/// Helper: The NOT NULL columns without a default. An `INTEGER PRIMARY KEY`
+/// is left out, since SQLite assigns it.
+fn required_columns(conn: &Connection, table_name: &str) -> rusqlite::Result<HashSet<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", quote_ident(table_name)))?;
+    let rows = stmt.query_map([], |row| {
+        let (name, decl): (String, String) = (row.get(1)?, row.get(2)?);
+        let not_null: bool = row.get(3)?;
+        let default: Option<String> = row.get(4)?;
+        let pk: i64 = row.get(5)?;
+        let rowid = pk > 0 && decl.eq_ignore_ascii_case("INTEGER");
+        Ok((not_null && default.is_none() && !rowid).then_some(name))
+    })?;
+    rows.filter_map(|row| row.transpose()).collect()
+}
+
+/// Helper: The columns that alone form the PRIMARY KEY or a UNIQUE index
+/// (partial indexes can't be `ON CONFLICT` targets without their `WHERE`, so
+/// they are left out)
+fn unique_columns(conn: &Connection, table_name: &str) -> rusqlite::Result<HashSet<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", quote_ident(table_name)))?;
+    let keys = stmt
+        .query_map([], |row| {
+            let (name, pk): (String, i64) = (row.get(1)?, row.get(5)?);
+            Ok((pk > 0).then_some(name))
+        })?
+        .filter_map(|row| row.transpose())
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let mut unique = HashSet::new();
+    if let [key] = keys.as_slice() {
+        unique.insert(key.clone());
+    }
+
+    let indexes: Vec<String> = conn
+        .prepare(&format!("PRAGMA index_list({})", quote_ident(table_name)))?
+        .query_map([], |row| {
+            let (name, is_unique, partial): (String, bool, bool) =
+                (row.get(1)?, row.get(2)?, row.get(4)?);
+            Ok((is_unique && !partial).then_some(name))
+        })?
+        .filter_map(|row| row.transpose())
+        .collect::<rusqlite::Result<_>>()?;
+    for index in indexes {
+        let columns: Vec<Option<String>> = conn
+            .prepare(&format!("PRAGMA index_info({})", quote_ident(&index)))?
+            .query_map([], |row| row.get(2))?
+            .collect::<rusqlite::Result<_>>()?;
+        // Expression indexes list their column as NULL
+        if let [Some(column)] = columns.as_slice() {
+            unique.insert(column.clone());
+        }
+    }
+    Ok(unique)
+}
+
+/// Helper: Rejects a new row that leaves out (or nulls) a required column
+fn check_required(
+    table: &TableState,
+    obj: &Map<String, Value>,
+) -> Result<(), (StatusCode, Json<Value>)> {
+    let mut errors = Map::new();
+    for column in &table.required {
+        if obj.get(column).is_none_or(|v| v.is_null()) {
+            errors.insert(column.clone(), Value::from("required"));
+        }
+    }
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err((
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(serde_json::json!({"errors": errors})),
+    ))
+}
+
+/// Helper: Binds the `{id}` path segment the way the key column compares:
+/// as an integer for INTEGER keys, as text (e.g. a UUID) otherwise
+fn id_value(
+    table: &TableState,
+    id: &str,
+) -> Result<rusqlite::types::Value, (StatusCode, Json<Value>)> {
+    if !table.integer_key {
+        return Ok(rusqlite::types::Value::Text(id.to_string()));
+    }
+    id.parse()
+        .map(rusqlite::types::Value::Integer)
+        .map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": format!("Invalid id: {}", id)})),
+            )
+        })
+}
+
+/// Helper: Runs the table's validators on a written row. With `whole_row`,
+/// columns missing from the row are checked as `null`.
+fn validate_row(
+    config: &TableConfig,
+    obj: &Map<String, Value>,
+    whole_row: bool,
+) -> Result<(), (StatusCode, Json<Value>)> {
+    let mut errors = Map::new();
+    for (column, checks) in &config.validators {
+        let value = match obj.get(column) {
+            Some(value) => value,
+            None if whole_row => &Value::Null,
+            None => continue,
+        };
+        if let Some(message) = checks.iter().find_map(|check| check(value).err()) {
+            errors.insert(column.clone(), Value::String(message));
+        }
+    }
+    for (column, kind) in &config.kinds {
+        let valid = match (kind, obj.get(column)) {
+            (ColumnKind::DateTime, Some(Value::String(text))) => normalize_rfc3339(text).is_some(),
+            (ColumnKind::DateTime, Some(value)) => value.is_null(),
+            _ => true,
+        };
+        if !valid && !errors.contains_key(column) {
+            errors.insert(column.clone(), Value::from("expected an RFC3339 timestamp"));
+        }
+    }
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err((
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(serde_json::json!({"errors": errors})),
+    ))
+}
+
+/// Helper: Rejects a column the table doesn't have, instead of letting
+/// SQLite fail the query with a 500
+fn known_column(table: &TableState, column: &str) -> Result<(), (StatusCode, Json<Value>)> {
+    if table.columns.contains(column) {
+        return Ok(());
+    }
+    Err((
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({"error": "unknown column", "column": column})),
+    ))
+}
+
+/// Helper: Enforces `with_scan_guard`. The row count is only taken when the
+/// plan contains a full scan, so indexed lookups stay cheap.
+fn check_scan_guard(
+    conn: &Connection,
+    table: &TableState,
+    sql: &str,
+    sql_params: &SqlParams,
+) -> Result<(), (StatusCode, Json<Value>)> {
+    let Some(max_rows) = table.scan_guard else {
+        return Ok(());
+    };
+    let internal = |e: rusqlite::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": e.to_string()})),
+        )
+    };
+
+    let scans = {
+        let mut stmt = conn
+            .prepare(&format!("EXPLAIN QUERY PLAN {}", sql))
+            .map_err(internal)?;
+        let details = stmt
+            .query_map(
+                rusqlite::params_from_iter(sql_params.iter().map(|p| p.as_ref())),
+                |row| row.get::<_, String>(3),
+            )
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<String>>>())
+            .map_err(internal)?;
+        details.iter().any(|d| d.starts_with("SCAN "))
+    };
+    if !scans {
+        return Ok(());
+    }
+
+    let rows: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM {}", quote_ident(&table.name)),
+            [],
+            |r| r.get(0),
+        )
+        .map_err(internal)?;
+    if rows as u64 > max_rows {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!(
+                    "Query would scan all {} rows of {}; filter on an indexed column or add an index",
+                    rows, table.name
+                )
+            })),
+        ));
+    }
+    Ok(())
+}
+
+/// Helper: Runs the query and writes a header row plus one record per row.
+/// The csv writer quotes fields containing commas, quotes or newlines.
+fn export_csv(
+    conn: &Connection,
+    table: &TableState,
+    scopes: &Scopes,
+    sql: &str,
+    sql_params: &SqlParams,
+) -> anyhow::Result<Vec<u8>> {
+    let mut stmt = conn.prepare(sql)?;
+    let visible: Vec<(usize, String)> = stmt
+        .column_names()
+        .into_iter()
+        .enumerate()
+        .filter(|(_, name)| !table.config.hidden_columns.contains(*name))
+        .map(|(i, name)| (i, name.to_string()))
+        .collect();
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(visible.iter().map(|(_, name)| name))?;
+
+    let mut rows = stmt.query(rusqlite::params_from_iter(
+        sql_params.iter().map(|p| p.as_ref()),
+    ))?;
+    while let Some(row) = rows.next()? {
+        let mut record = Vec::with_capacity(visible.len());
+        for (i, name) in &visible {
+            let value = value_to_json(row.get_ref(*i)?);
+            let value = mask_value(&table.config, scopes, name, value);
+            record.push(csv_field(value, &table.csv_null));
+        }
+        writer.write_record(&record)?;
+    }
+
+    Ok(writer.into_inner()?)
+}
+
+/// Helper: Renders one SQLite value as a CSV field
+fn csv_field(value: Value, null_token: &str) -> String {
+    match value {
+        Value::Null => null_token.to_string(),
+        Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+/// GET: Fetch a single record by id
+async fn handle_get_one(
+    State(table): State<Arc<TableState>>,
+    scopes: Scopes,
+    Path(id): Path<String>,
+    request_headers: axum::http::HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    let found = blocking(move || {
+        let id = match id_value(&table, &id) {
+            Ok(id) => id,
+            Err(e) => return e,
+        };
+        let (expand, embed) = match requested_relations(&table, &params) {
+            Ok(r) => r,
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": e})),
+                )
+            }
+        };
+
+        let conn = lock_conn(&table.conn);
+        let mut sql = format!(
+            "SELECT * FROM {} WHERE {} = ?",
+            quote_ident(&table.name),
+            quote_ident(&table.primary_key)
+        );
+        if hides_deleted(&table, &params) {
+            sql.push_str(" AND deleted_at IS NULL");
+        }
+        let typed = wants_typed(&params);
+        let found = conn.query_row(&sql, [id], |row| {
+            Ok(if typed {
+                row_to_typed_json(row, &table.config, &scopes)
+            } else {
+                row_to_json(row, &table.config, &scopes)
+            })
+        });
+        drop(conn);
+
+        match found {
+            Ok(row) => {
+                let mut rows = vec![row];
+                if let Err(e) = attach_relations(&expand, &embed, &mut rows, &scopes) {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    );
+                }
+                (StatusCode::OK, Json(rows.remove(0)))
+            }
+            Err(rusqlite::Error::QueryReturnedNoRows) => (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": "Record not found"})),
+            ),
+            Err(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": e.to_string()})),
+            ),
+        }
+    })
+    .await;
+    match found {
+        (StatusCode::OK, row) => with_etag(&request_headers, axum::http::HeaderMap::new(), row.0),
+        other => other.into_response(),
+    }
+}
+
+/// GET: Distinct values and their counts per requested column (Facets)
+async fn handle_facets(
+    State(table): State<Arc<TableState>>,
+    scopes: Scopes,
+    Query(params): Query<HashMap<String, String>>,
+) -> (StatusCode, Json<Value>) {
+    blocking(move || {
+        let conn = lock_conn(&table.conn);
+
+        let columns: Vec<&str> = match params.get("columns") {
+            Some(c) => c
+                .split(',')
+                .map(|c| c.trim())
+                .filter(|c| !c.is_empty())
+                .collect(),
+            None => Vec::new(),
+        };
+        if columns.is_empty() {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Missing 'columns' query parameter"})),
+            );
+        }
+
+        let mut facets = Map::new();
+        for col in columns {
+            // Hidden columns must not leak through their distinct values
+            let masked = table
+                .config
+                .masks
+                .get(col)
+                .is_some_and(|rule| !scopes.0.contains(&rule.scope));
+            if !is_valid_identifier(col) || table.config.hidden_columns.contains(col) || masked {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": format!("Invalid column: {}", col)})),
+                );
+            }
+            if let Err(e) = known_column(&table, col) {
+                return e;
+            }
+
+            let live_only = if hides_deleted(&table, &params) {
+                " WHERE deleted_at IS NULL"
+            } else {
+                ""
+            };
+            let quoted = quote_ident(col);
+            let sql = format!(
+                "SELECT {}, COUNT(*) FROM {}{} GROUP BY {} ORDER BY COUNT(*) DESC, {}",
+                quoted,
+                quote_ident(&table.name),
+                live_only,
+                quoted,
+                quoted
+            );
+            let mut stmt = match conn.prepare(&sql) {
+                Ok(s) => s,
+                Err(e) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    )
+                }
+            };
+
+            let rows = stmt.query_map([], |row| {
+                let count: i64 = row.get(1)?;
+                Ok(serde_json::json!({"value": value_to_json(row.get_ref(0)?), "count": count}))
+            });
+
+            match rows.and_then(|mapped| mapped.collect::<rusqlite::Result<Vec<Value>>>()) {
+                Ok(counts) => {
+                    facets.insert(col.to_string(), Value::from(counts));
+                }
+                Err(e) => {
+                    tracing::error!("facets of {}.{} failed: {}", table.name, col, e);
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    );
+                }
+            }
+        }
+
+        (StatusCode::OK, Json(Value::Object(facets)))
+    })
+    .await
+}
+
+/// GET /{table}/schema: Column names, types and registered descriptions.
+/// Hidden columns are left out.
+async fn handle_schema(State(table): State<Arc<TableState>>) -> (StatusCode, Json<Value>) {
+    blocking(move || {
+        let conn = lock_conn(&table.conn);
+
+        let sql = format!("PRAGMA table_info({})", quote_ident(&table.name));
+        let mut stmt = match conn.prepare(&sql) {
+            Ok(s) => s,
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": e.to_string()})),
+                )
+            }
+        };
+
+        let rows = stmt.query_map([], |row| {
+            let name: String = row.get(1)?;
+            let col_type: String = row.get(2)?;
+            let not_null: bool = row.get(3)?;
+            let primary_key: i64 = row.get(5)?;
+            Ok((name, col_type, not_null, primary_key > 0))
+        });
+
+        let columns: Vec<Value> =
+            match rows.and_then(|mapped| mapped.collect::<rusqlite::Result<Vec<_>>>()) {
+                Ok(columns) => columns
+                    .into_iter()
+                    .filter(|(name, ..)| !table.config.hidden_columns.contains(name))
+                    .map(|(name, col_type, not_null, primary_key)| {
+                        serde_json::json!({
+                            "description": table.config.descriptions.get(&name),
+                            "name": name,
+                            "type": col_type,
+                            "notnull": not_null,
+                            "pk": primary_key,
+                        })
+                    })
+                    .collect(),
+                Err(e) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    )
+                }
+            };
+
+        (
+            StatusCode::OK,
+            Json(serde_json::json!({"table": table.name, "columns": columns})),
+        )
+    })
+    .await
+}
+
+/// GET /{table}/search?q=...: Full-text search over the `enable_fts` columns,
+/// best matches first. Supports `_limit`/`_offset` and `_page`/`_per_page`.
+async fn handle_search(
+    State(table): State<Arc<TableState>>,
+    scopes: Scopes,
+    Query(params): Query<HashMap<String, String>>,
+) -> (StatusCode, Json<Value>) {
+    blocking(move || {
+        let query = match params.get("q").map(|q| q.trim()) {
+            Some(q) if !q.is_empty() => q.to_string(),
+            _ => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": "Missing 'q' query parameter"})),
+                )
+            }
+        };
+
+        let fts = quote_ident(&format!("{}_fts", table.name));
+        let mut sql = format!(
+            "SELECT t.* FROM {fts} JOIN {} t ON t.rowid = {fts}.rowid WHERE {fts} MATCH ?",
+            quote_ident(&table.name)
+        );
+        if hides_deleted(&table, &params) {
+            sql.push_str(" AND t.deleted_at IS NULL");
+        }
+        sql.push_str(" ORDER BY rank");
+
+        let mut sql_params: SqlParams = vec![Box::new(query)];
+        match pagination(&params) {
+            Ok(Some((limit, offset))) => {
+                sql.push_str(" LIMIT ? OFFSET ?");
+                sql_params.push(Box::new(limit));
+                sql_params.push(Box::new(offset));
+            }
+            Ok(None) => {}
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": e})),
+                )
+            }
+        }
+
+        let conn = lock_conn(&table.conn);
+        let mut stmt = match conn.prepare(&sql) {
+            Ok(s) => s,
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": e.to_string()})),
+                )
+            }
+        };
+        let rows = stmt
+            .query_map(
+                rusqlite::params_from_iter(sql_params.iter().map(|p| p.as_ref())),
+                |row| Ok(row_to_json(row, &table.config, &scopes)),
+            )
+            .and_then(|mapped| mapped.collect::<rusqlite::Result<Vec<Value>>>());
+
+        match rows {
+            Ok(rows) => (StatusCode::OK, Json(Value::from(rows))),
+            // The statement is valid, so a generic SQLITE_ERROR while stepping comes
+            // from a malformed MATCH expression (e.g. an unbalanced quote)
+            Err(rusqlite::Error::SqliteFailure(err, msg))
+                if err.code == rusqlite::ErrorCode::Unknown =>
+            {
+                let reason = msg.unwrap_or_else(|| err.to_string());
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": format!("Invalid search query: {}", reason)})),
+                )
+            }
+            Err(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": e.to_string()})),
+            ),
+        }
+    })
+    .await
+}
+
+/// GET /{table}/ddl: The `CREATE TABLE` (or `CREATE VIEW`) statement as stored by SQLite.
+/// The statement is returned verbatim, so it names hidden columns too.
+async fn handle_ddl(State(table): State<Arc<TableState>>) -> (StatusCode, Json<Value>) {
+    blocking(move || {
+        let conn = lock_conn(&table.conn);
+
+        let ddl = conn.query_row(
+            "SELECT sql FROM sqlite_master WHERE type IN ('table', 'view') AND name = ?",
+            [&table.name],
+            |row| row.get::<_, Option<String>>(0),
+        );
+        match ddl {
+            Ok(sql) => (
+                StatusCode::OK,
+                Json(serde_json::json!({"table": table.name, "sql": sql})),
+            ),
+            Err(rusqlite::Error::QueryReturnedNoRows) => (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": "Table not found"})),
+            ),
+            Err(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": e.to_string()})),
+            ),
+        }
+    })
+    .await
+}
+
+/// GET /{table}/subscribe: WebSocket that pushes `{"table", "op", "id"}` for every
+/// row inserted, updated or deleted in the table
+async fn handle_subscribe(
+    State(table): State<Arc<TableState>>,
+    ws: axum::extract::ws::WebSocketUpgrade,
+) -> axum::response::Response {
+    // Subscribe before the handshake so no change in between is missed
+    let rx = table.changes.subscribe();
+    let name = table.name.clone();
+    ws.on_upgrade(move |socket| forward_changes(socket, name, rx))
+}
+
+/// Sends the table's change events to one socket until either side goes away.
+/// Takes only the table name: holding the `TableState` would keep the
+/// write queues open after shutdown.
+async fn forward_changes(
+    mut socket: axum::extract::ws::WebSocket,
+    table_name: String,
+    mut rx: broadcast::Receiver<ChangeEvent>,
+) {
+    use axum::extract::ws::Message;
+
+    loop {
+        tokio::select! {
+            event = rx.recv() => match event {
+                Ok(event) if event.table == table_name => {
+                    let msg = serde_json::json!({
+                        "table": event.table,
+                        "op": event.op,
+                        "id": event.id,
+                    });
+                    if socket.send(Message::Text(msg.to_string().into())).await.is_err() {
+                        break;
+                    }
+                }
+                // Other tables, or events dropped because this socket fell behind
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// POST: Create new record (SECURE VERSION)
+async fn handle_post(
+    State(table): State<Arc<TableState>>,
+    axum::extract::OriginalUri(uri): axum::extract::OriginalUri,
+    JsonBody(payload): JsonBody,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    let validated = {
+        let table = Arc::clone(&table);
+        blocking(move || new_row(&table, payload)).await
+    };
+    let obj = match validated {
+        Ok(obj) => obj,
+        Err(e) => return e.into_response(),
+    };
+
+    // Batched tables answer before the row is written
+    if let Some(queue) = &table.queue {
+        return match queue.send(obj).await {
+            Ok(()) => (
+                StatusCode::ACCEPTED,
+                Json(serde_json::json!({"status": "accepted", "message": "Record queued"})),
+            ),
+            Err(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({"error": "Write queue is closed"})),
+            ),
+        }
+        .into_response();
+    }
+
+    blocking(move || {
+        let conn = lock_conn(&table.conn);
+        match insert_row(&conn, &table.name, &table.config, table.busy_retry, &obj) {
+            Ok(_) => {
+                // The request path already carries any prefix or mount point
+                let id = created_id(&table, &conn, &obj);
+                let segment = match &id {
+                    Value::String(text) => percent_encode(text),
+                    other => other.to_string(),
+                };
+                let location = format!("{}/{}", uri.path().trim_end_matches('/'), segment);
+                (
+                    StatusCode::CREATED,
+                    [(axum::http::header::LOCATION, location)],
+                    Json(
+                        serde_json::json!({"status": "success", "message": "Record created", "id": id, "affected": 1}),
+                    ),
+                )
+                    .into_response()
+            }
+            Err(e) => match e.downcast::<rusqlite::Error>() {
+                Ok(e) => write_error(e),
+                Err(e) => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": e.to_string()})),
+                ),
+            }
+            .into_response(),
+        }
+    })
+    .await
+}
+
+/// Helper: The key of a row `insert_row` just wrote: the given value for
+/// non-integer keys, the new rowid otherwise
+fn created_id(table: &TableState, conn: &Connection, obj: &Map<String, Value>) -> Value {
+    match obj.get(&table.primary_key) {
+        Some(id) if !table.integer_key => id.clone(),
+        _ => Value::from(conn.last_insert_rowid()),
+    }
+}
+
+/// Helper: Escapes everything but unreserved characters for use in a URL path
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Helper: Stamps and validates the body of a POST, ready for `insert_row`
+fn new_row(
+    table: &TableState,
+    mut payload: Value,
+) -> Result<Map<String, Value>, (StatusCode, Json<Value>)> {
+    apply_timestamps(table, &mut payload, &["created_at", "updated_at"]);
+
+    let obj = match payload {
+        Value::Object(obj) => obj,
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Invalid JSON format"})),
+            ))
+        }
+    };
+    if obj.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Empty JSON body"})),
+        ));
+    }
+    for key in obj.keys() {
+        if !is_valid_identifier(key) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": format!("Invalid column: {}", key)})),
+            ));
+        }
+        known_column(table, key)?;
+    }
+    check_required(table, &obj)?;
+    validate_row(&table.config, &obj, true)?;
+    Ok(obj)
+}
+
+/// Helper: Inserts one JSON object as a row (column names must already be validated)
+fn insert_row(
+    conn: &Connection,
+    table_name: &str,
+    config: &TableConfig,
+    retry: BusyRetry,
+    obj: &Map<String, Value>,
+) -> anyhow::Result<usize> {
+    let keys: Vec<&str> = obj.keys().map(|k| k.as_str()).collect();
+    let placeholders = vec!["?"; keys.len()].join(", ");
+    let sql = format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        quote_ident(table_name),
+        quoted_list(&keys),
+        placeholders
+    );
+
+    let vals = body_values(config, obj)?;
+    Ok(retry_busy(retry, || {
+        conn.execute(&sql, rusqlite::params_from_iter(vals.iter()))
+    })?)
+}
+
+/// Background task: Inserts queued rows in batched transactions until the queue closes
+async fn run_write_queue(
+    table_name: String,
+    conn: Arc<Mutex<Connection>>,
+    config: TableConfig,
+    retry: BusyRetry,
+    mut rx: mpsc::Receiver<Map<String, Value>>,
+) {
+    let Some(batch) = config.batch else {
+        return;
+    };
+    let table_name = Arc::new(table_name);
+    let config = Arc::new(config);
+    let mut pending = Vec::with_capacity(batch.size);
+    // The first tick comes a full interval from now, not right away
+    let start = tokio::time::Instant::now() + batch.interval;
+    let mut ticker = tokio::time::interval_at(start, batch.interval);
+
+    loop {
+        let open = tokio::select! {
+            row = rx.recv() => match row {
+                Some(row) => {
+                    pending.push(row);
+                    if pending.len() < batch.size {
+                        continue;
+                    }
+                    true
+                }
+                None => false,
+            },
+            _ = ticker.tick() => true,
+        };
+
+        if !pending.is_empty() {
+            let (table_name, conn, config) = (
+                Arc::clone(&table_name),
+                Arc::clone(&conn),
+                Arc::clone(&config),
+            );
+            let rows = std::mem::take(&mut pending);
+            blocking(move || flush_batch(&table_name, &conn, &config, retry, rows)).await;
+        }
+        if !open {
+            break;
+        }
+    }
+}
+
+/// Helper: Writes one batch in a single transaction, skipping rows that fail
+fn flush_batch(
+    table_name: &str,
+    conn: &Mutex<Connection>,
+    config: &TableConfig,
+    retry: BusyRetry,
+    rows: Vec<Map<String, Value>>,
+) {
+    let mut conn = lock_conn(conn);
+    let tx = match conn.transaction() {
+        Ok(tx) => tx,
+        Err(e) => {
+            tracing::error!(
+                "Dropping {} queued rows for '{}': {}",
+                rows.len(),
+                table_name,
+                e
+            );
+            return;
+        }
+    };
+    for row in &rows {
+        if let Err(e) = insert_row(&tx, table_name, config, retry, row) {
+            tracing::warn!("Skipping queued row for '{}': {}", table_name, e);
+        }
+    }
+    if let Err(e) = tx.commit() {
+        tracing::error!("Failed to commit batch for '{}': {}", table_name, e);
+    }
+}
+
+/// POST /{table}/import: Inserts every row of a CSV body (header row = column names)
+/// in one transaction. Bad rows are reported with their line number and skipped,
+/// unless `?strict=true` is given, in which case nothing is inserted.
+async fn handle_import_csv(
+    State(table): State<Arc<TableState>>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: axum::http::HeaderMap,
+    body: String,
+) -> (StatusCode, Json<Value>) {
+    blocking(move || {
+        let is_csv = headers
+            .get(axum::http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/csv"));
+        if !is_csv {
+            return (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                Json(serde_json::json!({"error": "Expected Content-Type: text/csv"})),
+            );
+        }
+        let strict = params.get("strict").is_some_and(|v| v == "true");
+
+        let mut reader = csv::Reader::from_reader(body.as_bytes());
+        let columns: Vec<String> = match reader.headers() {
+            Ok(h) => h.iter().map(|c| c.trim().to_string()).collect(),
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": format!("Invalid CSV header: {}", e)})),
+                )
+            }
+        };
+        if columns.is_empty() {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Missing CSV header"})),
+            );
+        }
+        for col in &columns {
+            if !is_valid_identifier(col) || col.is_empty() {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": format!("Invalid column: {}", col)})),
+                );
+            }
+            if let Err(e) = known_column(&table, col) {
+                return e;
+            }
+        }
+
+        let mut conn = lock_conn(&table.conn);
+        let tx = match conn.transaction() {
+            Ok(tx) => tx,
+            Err(e) => return write_error(e),
+        };
+
+        let mut inserted = 0;
+        let mut errors = Vec::new();
+        for record in reader.records() {
+            let record = match record {
+                Ok(r) => r,
+                Err(e) => {
+                    let line = e.position().map(|p| p.line());
+                    errors.push(serde_json::json!({"line": line, "error": e.to_string()}));
+                    continue;
+                }
+            };
+            let line = record.position().map(|p| p.line());
+
+            // Empty and NULL fields are left out so the column gets its default (or NULL)
+            let mut row = Map::new();
+            for (col, field) in columns.iter().zip(record.iter()) {
+                if !field.is_empty() && field != table.csv_null {
+                    row.insert(col.clone(), Value::String(field.to_string()));
+                }
+            }
+            if row.is_empty() {
+                errors.push(serde_json::json!({"line": line, "error": "Empty row"}));
+                continue;
+            }
+            stamp_row(&table, &mut row, &["created_at", "updated_at"]);
+            match insert_row(&tx, &table.name, &table.config, table.busy_retry, &row) {
+                Ok(_) => inserted += 1,
+                Err(e) => errors.push(serde_json::json!({"line": line, "error": e.to_string()})),
+            }
+        }
+
+        if strict && !errors.is_empty() {
+            // Dropping the transaction rolls back every row
+            drop(tx);
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"inserted": 0, "errors": errors, "affected": 0})),
+            );
+        }
+        if let Err(e) = tx.commit() {
+            return write_error(e);
+        }
+
+        (
+            StatusCode::OK,
+            Json(serde_json::json!({"inserted": inserted, "errors": errors, "affected": inserted})),
+        )
+    })
+    .await
+}
+
+/// POST: Insert or update a record based on a conflict column (UPSERT)
+async fn handle_upsert(
+    State(table): State<Arc<TableState>>,
+    Query(params): Query<HashMap<String, String>>,
+    JsonBody(mut payload): JsonBody,
+) -> (StatusCode, Json<Value>) {
+    blocking(move || {
+        let conn = lock_conn(&table.conn);
+        apply_timestamps(&table, &mut payload, &["created_at", "updated_at"]);
+
+        let conflict = match params.get("conflict") {
+            Some(c) if is_valid_identifier(c) => c,
+            Some(_) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": "Invalid conflict column"})),
+                )
+            }
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": "Missing 'conflict' query parameter"})),
+                )
+            }
+        };
+
+        if let Some(obj) = payload.as_object() {
+            let keys: Vec<String> = obj.keys().cloned().collect();
+            for key in &keys {
+                if !is_valid_identifier(key) {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(serde_json::json!({"error": format!("Invalid column: {}", key)})),
+                    );
+                }
+                if let Err(e) = known_column(&table, key) {
+                    return e;
+                }
+            }
+
+            // SQLite only accepts a PRIMARY KEY or UNIQUE column as the target
+            if !table.unique.contains(conflict) {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(
+                        serde_json::json!({"error": format!("Conflict column must be UNIQUE or PRIMARY KEY: {}", conflict)}),
+                    ),
+                );
+            }
+            if !obj.contains_key(conflict) {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(
+                        serde_json::json!({"error": format!("Body must contain conflict column: {}", conflict)}),
+                    ),
+                );
+            }
+            // The row may be inserted, so it must pass the same checks as a POST
+            if let Err(e) = check_required(&table, obj) {
+                return e;
+            }
+            if let Err(e) = validate_row(&table.config, obj, true) {
+                return e;
+            }
+
+            let placeholders: Vec<String> = keys.iter().map(|_| "?".to_string()).collect();
+            let updates: Vec<String> = keys
+                .iter()
+                .filter(|k| *k != conflict)
+                // An update must keep the original creation time
+                .filter(|k| !(table.config.timestamps && *k == "created_at"))
+                .map(|k| format!("{0} = excluded.{0}", quote_ident(k)))
+                .collect();
+            let action = if updates.is_empty() {
+                "NOTHING".to_string()
+            } else {
+                format!("UPDATE SET {}", updates.join(", "))
+            };
+            let sql = format!(
+                "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT({}) DO {}",
+                quote_ident(&table.name),
+                quoted_list(&keys),
+                placeholders.join(", "),
+                quote_ident(conflict),
+                action
+            );
+
+            let vals = match body_values(&table.config, obj) {
+                Ok(v) => v,
+                Err(e) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    )
+                }
+            };
+
+            match retry_busy(table.busy_retry, || {
+                conn.execute(&sql, rusqlite::params_from_iter(vals.iter()))
+            }) {
+                Ok(affected) => (
+                    StatusCode::OK,
+                    Json(
+                        serde_json::json!({"status": "success", "message": "Record upserted", "affected": affected}),
+                    ),
+                ),
+                Err(e) => write_error(e),
+            }
+        } else {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Invalid JSON format"})),
+            )
+        }
+    })
+    .await
+}
+
+/// PUT: Update record (SECURE VERSION)
+async fn handle_put(
+    State(table): State<Arc<TableState>>,
+    Path(id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    JsonBody(payload): JsonBody,
+) -> (StatusCode, Json<Value>) {
+    blocking(move || {
+        let id = match id_value(&table, &id) {
+            Ok(id) => id,
+            Err(e) => return e,
+        };
+        let live_only = hides_deleted(&table, &params);
+        let conn = lock_conn(&table.conn);
+        update_row(&table, &conn, id, payload, live_only)
+    })
+    .await
+}
+
+/// Helper: Updates the given columns of the row with key `id` (a PUT).
+/// With `live_only`, soft-deleted rows are left alone and answer 404.
+fn update_row(
+    table: &TableState,
+    conn: &Connection,
+    id: rusqlite::types::Value,
+    mut payload: Value,
+    live_only: bool,
+) -> (StatusCode, Json<Value>) {
+    apply_timestamps(table, &mut payload, &["updated_at"]);
+
+    if let Some(obj) = payload.as_object() {
+        for key in obj.keys() {
+            if !is_valid_identifier(key) {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": "Invalid column name"})),
+                );
+            }
+            if let Err(e) = known_column(table, key) {
+                return e;
+            }
+        }
+        if let Err(e) = validate_row(&table.config, obj, false) {
+            return e;
+        }
+
+        let updates: Vec<String> = obj
+            .keys()
+            .map(|k| format!("{} = ?", quote_ident(k)))
+            .collect();
+        let mut sql = format!(
+            "UPDATE {} SET {} WHERE {} = ?",
+            quote_ident(&table.name),
+            updates.join(", "),
+            quote_ident(&table.primary_key)
+        );
+        if live_only {
+            sql.push_str(" AND deleted_at IS NULL");
+        }
+
+        let mut params: Vec<rusqlite::types::Value> = match body_values(&table.config, obj) {
+            Ok(v) => v,
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": e.to_string()})),
+                )
+            }
+        };
+        params.push(id);
+
+        match retry_busy(table.busy_retry, || {
+            conn.execute(&sql, rusqlite::params_from_iter(params.iter()))
+        }) {
+            Ok(affected) => {
+                if affected == 0 {
+                    (
+                        StatusCode::NOT_FOUND,
+                        Json(serde_json::json!({"error": "Record not found"})),
+                    )
+                } else {
+                    (
+                        StatusCode::OK,
+                        Json(
+                            serde_json::json!({"status": "success", "message": "Record updated", "affected": affected}),
+                        ),
+                    )
+                }
+            }
+            Err(e) => write_error(e),
+        }
+    } else {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Invalid JSON format"})),
+        )
+    }
+}
+
+/// PATCH: Apply a JSON merge patch (RFC 7396, `application/merge-patch+json`)
+/// to a record. `null` clears a column, absent keys are left unchanged, and
+/// objects are merged into `ColumnKind::Json` columns holding JSON objects.
+async fn handle_patch(
+    State(table): State<Arc<TableState>>,
+    Path(id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: axum::http::HeaderMap,
+    body: axum::body::Bytes,
+) -> (StatusCode, Json<Value>) {
+    blocking(move || {
+        let id = match id_value(&table, &id) {
+            Ok(id) => id,
+            Err(e) => return e,
+        };
+        let is_merge_patch = headers
+            .get(axum::http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/merge-patch+json"));
+        if !is_merge_patch {
+            return (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                Json(
+                    serde_json::json!({"error": "Expected Content-Type: application/merge-patch+json"}),
+                ),
+            );
+        }
+
+        let mut patch_doc = match serde_json::from_slice::<Value>(&body) {
+            Ok(Value::Object(obj)) => Value::Object(obj),
+            _ => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": "Invalid JSON format"})),
+                )
+            }
+        };
+        apply_timestamps(&table, &mut patch_doc, &["updated_at"]);
+        let patch_obj = patch_doc.as_object().cloned().unwrap_or_default();
+        if patch_obj.is_empty() {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Empty JSON body"})),
+            );
+        }
+        for key in patch_obj.keys() {
+            if !is_valid_identifier(key) {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": "Invalid column name"})),
+                );
+            }
+            if let Err(e) = known_column(&table, key) {
+                return e;
+            }
+        }
+        if let Err(e) = validate_row(&table.config, &patch_obj, false) {
+            return e;
+        }
+
+        let live_only = if hides_deleted(&table, &params) {
+            " AND deleted_at IS NULL"
+        } else {
+            ""
+        };
+        let conn = lock_conn(&table.conn);
+
+        // The patch is applied to the full row, hidden columns included
+        let sql = format!(
+            "SELECT * FROM {} WHERE {} = ?{}",
+            quote_ident(&table.name),
+            quote_ident(&table.primary_key),
+            live_only
+        );
+        let mut row = match conn.query_row(&sql, [&id], |row| {
+            Ok(row_to_json(
+                row,
+                &TableConfig::default(),
+                &Scopes::default(),
+            ))
+        }) {
+            Ok(row) => row,
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(serde_json::json!({"error": "Record not found"})),
+                )
+            }
+            Err(e) => return write_error(e),
+        };
+
+        // JSON columns holding an object are merged; anything else is replaced
+        if let Some(cols) = row.as_object_mut() {
+            for (key, value) in &patch_obj {
+                if !value.is_object() || table.config.kinds.get(key) != Some(&ColumnKind::Json) {
+                    continue;
+                }
+                if let Some(Value::String(text)) = cols.get(key) {
+                    if let Ok(parsed @ Value::Object(_)) = serde_json::from_str::<Value>(text) {
+                        cols.insert(key.clone(), parsed);
+                    }
+                }
+            }
+        }
+        merge_patch(&mut row, &patch_doc);
+
+        let mut params = Vec::with_capacity(patch_obj.len() + 1);
+        for key in patch_obj.keys() {
+            let value = row.get(key).cloned().unwrap_or(Value::Null);
+            let value = match value {
+                Value::String(plain) if table.config.hashed_columns.contains(key) => {
+                    match hash_secret(&plain) {
+                        Ok(hash) => Value::String(hash),
+                        Err(e) => {
+                            return (
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                Json(serde_json::json!({"error": e.to_string()})),
+                            )
+                        }
+                    }
+                }
+                other => stored_value(&table.config, key, &other),
+            };
+            params.push(json_to_sql(&value));
+        }
+        params.push(id);
+
+        let updates: Vec<String> = patch_obj
+            .keys()
+            .map(|k| format!("{} = ?", quote_ident(k)))
+            .collect();
+        let sql = format!(
+            "UPDATE {} SET {} WHERE {} = ?{}",
+            quote_ident(&table.name),
+            updates.join(", "),
+            quote_ident(&table.primary_key),
+            live_only
+        );
+        match retry_busy(table.busy_retry, || {
+            conn.execute(&sql, rusqlite::params_from_iter(params.iter()))
+        }) {
+            Ok(affected) => (
+                StatusCode::OK,
+                Json(
+                    serde_json::json!({"status": "success", "message": "Record updated", "affected": affected}),
+                ),
+            ),
+            Err(e) => write_error(e),
+        }
+    })
+    .await
+}
+
+/// Helper: RFC 7396 merge: `null` removes a key, objects merge recursively,
+/// anything else replaces the target
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    if let Value::Object(map) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                map.remove(key);
+            } else {
+                merge_patch(map.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// DELETE: Delete record (SECURE VERSION)
+async fn handle_delete(
+    State(table): State<Arc<TableState>>,
+    Path(id): Path<String>,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    let no_content = table.no_content_deletes;
+    let deleted = blocking(move || {
+        let id = match id_value(&table, &id) {
+            Ok(id) => id,
+            Err(e) => return e,
+        };
+        let mut conn = lock_conn(&table.conn);
+
+        // Cascading children go first, in one transaction with the parent
+        let result = retry_busy(table.busy_retry, || {
+            let tx = conn.transaction()?;
+            let affected = delete_row(&table, &tx, &id)?;
+            if affected > 0 {
+                tx.commit()?;
+            }
+            Ok(affected)
+        });
+
+        match result {
+            Ok(affected) => {
+                if affected == 0 {
+                    (
+                        StatusCode::NOT_FOUND,
+                        Json(serde_json::json!({"error": "Record not found"})),
+                    )
+                } else {
+                    let id = id_json(&id);
+                    (
+                        StatusCode::OK,
+                        Json(
+                            serde_json::json!({"status": "success", "message": "Record deleted", "id": id, "affected": affected}),
+                        ),
+                    )
+                }
+            }
+            Err(e) => write_error(e),
+        }
+    })
+    .await;
+    match deleted {
+        (StatusCode::OK, _) if no_content => StatusCode::NO_CONTENT.into_response(),
+        other => other.into_response(),
+    }
+}
+
+/// Helper: Echoes a key as stored: a number for integer keys, else a string
+fn id_json(id: &rusqlite::types::Value) -> Value {
+    match id {
+        rusqlite::types::Value::Integer(i) => Value::from(*i),
+        rusqlite::types::Value::Text(t) => Value::from(t.as_str()),
+        _ => Value::Null,
+    }
+}
+
+/// Helper: Deletes the row with key `id` and, before it, the rows of its
+/// cascading children. Run it in a transaction so a failure undoes both.
+fn delete_row(
+    table: &TableState,
+    conn: &Connection,
+    id: &rusqlite::types::Value,
+) -> rusqlite::Result<usize> {
+    let now = now_rfc3339(table.clock.as_ref());
+    let target = format!("{} = ?", quote_ident(&table.primary_key));
+    let run = |sql: &str, soft: bool| {
+        if soft {
+            conn.execute(sql, rusqlite::params![now, id])
+        } else {
+            conn.execute(sql, [id])
+        }
+    };
+    for child in &table.cascades {
+        let matching = format!(
+            "{col} IN (SELECT {col} FROM {} WHERE {})",
+            quote_ident(&table.name),
+            target,
+            col = quote_ident(&child.column)
+        );
+        let sql = delete_sql(&child.name, child.config.soft_delete, &matching);
+        run(&sql, child.config.soft_delete)?;
+    }
+    let parent = delete_sql(&table.name, table.config.soft_delete, &target);
+    run(&parent, table.config.soft_delete)
+}
+
+/// Helper: Deletes the rows matching `condition`, or marks them deleted
+/// (binding the time as the first parameter) on soft-delete tables
+fn delete_sql(table_name: &str, soft_delete: bool, condition: &str) -> String {
+    if soft_delete {
+        format!(
+            "UPDATE {} SET deleted_at = ? WHERE {} AND deleted_at IS NULL",
+            quote_ident(table_name),
+            condition
+        )
+    } else {
+        format!(
+            "DELETE FROM {} WHERE {}",
+            quote_ident(table_name),
+            condition
+        )
+    }
+}
+
+/// POST /_batch: Runs a list of writes in order, in one transaction.
+/// Each operation is `{"op": "post"|"put"|"delete", "table", "id", "data"}`
+/// and gets its own savepoint: a failed one is undone and, unless it has
+/// `"continue_on_error": true`, rolls back the whole batch.
+async fn handle_batch(
+    tables: Arc<HashMap<String, Arc<TableState>>>,
+    JsonBody(payload): JsonBody,
+) -> (StatusCode, Json<Value>) {
+    blocking(move || {
+        let invalid = |index: usize, error: String| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": error, "index": index})),
+            )
+        };
+        let Value::Array(list) = payload else {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Expected an array of operations"})),
+            );
+        };
+
+        // 1. Every operation is checked before anything is written
+        let mut ops: Vec<BatchOp> = Vec::with_capacity(list.len());
+        for (index, item) in list.into_iter().enumerate() {
+            let table = match item.get("table").and_then(|t| t.as_str()) {
+                Some(name) => match tables.get(name) {
+                    Some(table) => Arc::clone(table),
+                    None => return invalid(index, format!("Unknown table: {}", name)),
+                },
+                None => return invalid(index, "Missing table".to_string()),
+            };
+            let op = match item.get("op").and_then(|o| o.as_str()) {
+                Some(op @ ("post" | "put" | "delete")) => op.to_string(),
+                _ => return invalid(index, "op must be post, put or delete".to_string()),
+            };
+            let id = match item.get("id") {
+                Some(Value::Number(n)) => Some(n.to_string()),
+                Some(Value::String(s)) => Some(s.clone()),
+                None | Some(Value::Null) => None,
+                Some(_) => return invalid(index, "id must be a number or a string".to_string()),
+            };
+            if op != "post" && id.is_none() {
+                return invalid(index, format!("Missing id for {}", op));
+            }
+            if let Some(first) = ops.first() {
+                if !Arc::ptr_eq(&first.table.conn, &table.conn) {
+                    return invalid(
+                        index,
+                        "A batch can only write tables of one database".to_string(),
+                    );
+                }
+            }
+            ops.push(BatchOp {
+                table,
+                op,
+                id,
+                data: item.get("data").cloned().unwrap_or(Value::Null),
+                continue_on_error: item
+                    .get("continue_on_error")
+                    .and_then(|c| c.as_bool())
+                    .unwrap_or(false),
+            });
+        }
+        let Some(first) = ops.first() else {
+            return (StatusCode::OK, Json(serde_json::json!({"results": []})));
+        };
+
+        // 2. Taking the write lock up front keeps SQLITE_BUSY out of the operations
+        let conn = lock_conn(&first.table.conn);
+        let begin = retry_busy(first.table.busy_retry, || {
+            rusqlite::Transaction::new_unchecked(&conn, rusqlite::TransactionBehavior::Immediate)
+        });
+        let mut tx = match begin {
+            Ok(tx) => tx,
+            Err(e) => return write_error(e),
+        };
+
+        let mut results = Vec::with_capacity(ops.len());
+        for (index, op) in ops.iter().enumerate() {
+            let savepoint = match tx.savepoint() {
+                Ok(sp) => sp,
+                Err(e) => return write_error(e),
+            };
+            let (status, Json(body)) = run_batch_op(&savepoint, op);
+            if status.is_success() {
+                if let Err(e) = savepoint.commit() {
+                    return write_error(e);
+                }
+            }
+            // A savepoint that isn't committed is rolled back when dropped
+            results.push(serde_json::json!({"status": status.as_u16(), "body": body}));
+            if !status.is_success() && !op.continue_on_error {
+                return (
+                    status,
+                    Json(serde_json::json!({
+                        "error": "Batch rolled back",
+                        "index": index,
+                        "results": results,
+                    })),
+                );
+            }
+        }
+
+        match tx.commit() {
+            Ok(()) => (
+                StatusCode::OK,
+                Json(serde_json::json!({"results": results})),
+            ),
+            Err(e) => write_error(e),
+        }
+    })
+    .await
+}
+
+/// One checked operation of a `/_batch` request
+struct BatchOp {
+    table: Arc<TableState>,
+    op: String,
+    id: Option<String>,
+    data: Value,
+    continue_on_error: bool,
+}
+
+/// Helper: Runs one batch operation on the batch's transaction. Answers the
+/// way the matching route would, plus the key for created and deleted rows.
+fn run_batch_op(conn: &Connection, op: &BatchOp) -> (StatusCode, Json<Value>) {
+    let table = &op.table;
+    let id = match op.id.as_deref().map(|id| id_value(table, id)).transpose() {
+        Ok(id) => id,
+        Err(e) => return e,
+    };
+    match (op.op.as_str(), id) {
+        ("post", _) => {
+            let obj = match new_row(table, op.data.clone()) {
+                Ok(obj) => obj,
+                Err(e) => return e,
+            };
+            let policy = BusyRetry::default();
+            if let Err(e) = insert_row(conn, &table.name, &table.config, policy, &obj) {
+                return match e.downcast::<rusqlite::Error>() {
+                    Ok(e) => write_error(e),
+                    Err(e) => (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    ),
+                };
+            }
+            let id = created_id(table, conn, &obj);
+            (
+                StatusCode::CREATED,
+                Json(
+                    serde_json::json!({"status": "success", "message": "Record created", "id": id, "affected": 1}),
+                ),
+            )
+        }
+        ("put", Some(id)) => update_row(table, conn, id, op.data.clone(), table.config.soft_delete),
+        ("delete", Some(id)) => match delete_row(table, conn, &id) {
+            Ok(0) => (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": "Record not found"})),
+            ),
+            Ok(affected) => (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "status": "success",
+                    "message": "Record deleted",
+                    "id": id_json(&id),
+                    "affected": affected,
+                })),
+            ),
+            Err(e) => write_error(e),
+        },
+        _ => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Invalid operation"})),
+        ),
+    }
+}
+
+/// POST /_backup: Snapshots the main database to a new file
+async fn handle_backup(backup: BackupFn, dest: PathBuf) -> (StatusCode, Json<Value>) {
+    let path = dest.display().to_string();
+    match blocking(move || backup(&dest)).await {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(serde_json::json!({"status": "success", "path": path})),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": format!("Backup failed: {}", e)})),
+        ),
+    }
+}
+
+/// Helper: Copies `source` into the file at `dest` with the online backup
+/// API, in a single step so the copy is one consistent snapshot
+fn copy_database(source: &Connection, dest: &std::path::Path) -> anyhow::Result<()> {
+    // Moves committed WAL frames into the main file where possible; the
+    // backup reads through the WAL either way, so nothing is missed
+    source.query_row("PRAGMA wal_checkpoint(PASSIVE)", [], |_| Ok(()))?;
+
+    let mut target = Connection::open(dest)?;
+    let backup = rusqlite::backup::Backup::new(source, &mut target)?;
+    loop {
+        match backup.step(-1)? {
+            rusqlite::backup::StepResult::Done => return Ok(()),
+            // A writer holds the lock; try again shortly
+            _ => std::thread::sleep(Duration::from_millis(50)),
+        }
+    }
+}
+
+/// DELETE (no id): Removes every row, but only when the request carries an
+/// `X-Confirm-Truncate` header. Soft-delete tables mark every row deleted.
+async fn handle_truncate(
+    State(table): State<Arc<TableState>>,
+    headers: axum::http::HeaderMap,
+) -> (StatusCode, Json<Value>) {
+    blocking(move || {
+        if !headers.contains_key("x-confirm-truncate") {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(
+                    serde_json::json!({"error": "Deleting every row requires an X-Confirm-Truncate header"}),
+                ),
+            );
+        }
+
+        let conn = lock_conn(&table.conn);
+        let result = if table.config.soft_delete {
+            let sql = format!(
+                "UPDATE {} SET deleted_at = ? WHERE deleted_at IS NULL",
+                quote_ident(&table.name)
+            );
+            let now = now_rfc3339(table.clock.as_ref());
+            retry_busy(table.busy_retry, || conn.execute(&sql, [&now]))
+        } else {
+            let sql = format!("DELETE FROM {}", quote_ident(&table.name));
+            retry_busy(table.busy_retry, || conn.execute(&sql, []))
+        };
+
+        match result {
+            Ok(deleted) => (
+                StatusCode::OK,
+                Json(serde_json::json!({"status": "success", "deleted": deleted, "affected": deleted})),
+            ),
+            Err(e) => write_error(e),
+        }
+    })
+    .await
+}
+
+/// Helper: Refuses a filtered bulk write unless `X-Confirm-Bulk` is present
+fn confirm_bulk(headers: &axum::http::HeaderMap) -> Result<(), (StatusCode, Json<Value>)> {
+    if headers.contains_key("x-confirm-bulk") {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "Bulk writes require an X-Confirm-Bulk header"})),
+        ))
+    }
+}
+
+/// DELETE /{table}?filters: Deletes every row matching the filters (marks them
+/// deleted on soft-delete tables). Without filters this is a truncate.
+async fn handle_bulk_delete(
+    State(table): State<Arc<TableState>>,
+    params: QueryParams,
+    headers: axum::http::HeaderMap,
+) -> (StatusCode, Json<Value>) {
+    if !has_filters(&params) {
+        return handle_truncate(State(table), headers).await;
+    }
+    blocking(move || {
+        if let Err(e) = confirm_bulk(&headers) {
+            return e;
+        }
+        let (filters, filter_params) = match filter_conditions(&table, &params) {
+            Ok(f) => f,
+            Err(e) => return e,
+        };
+
+        let conn = lock_conn(&table.conn);
+        let now = now_rfc3339(table.clock.as_ref());
+        let sql = if table.config.soft_delete {
+            format!(
+                "UPDATE {} SET deleted_at = ? WHERE {}",
+                quote_ident(&table.name),
+                filters.join(" AND ")
+            )
+        } else {
+            format!(
+                "DELETE FROM {} WHERE {}",
+                quote_ident(&table.name),
+                filters.join(" AND ")
+            )
+        };
+        let mut sql_params: Vec<&dyn ToSql> = Vec::new();
+        if table.config.soft_delete {
+            sql_params.push(&now);
+        }
+        sql_params.extend(filter_params.iter().map(|p| p.as_ref()));
+
+        match retry_busy(table.busy_retry, || {
+            conn.execute(&sql, sql_params.as_slice())
+        }) {
+            Ok(deleted) => (
+                StatusCode::OK,
+                Json(serde_json::json!({"status": "success", "deleted": deleted, "affected": deleted})),
+            ),
+            Err(e) => write_error(e),
+        }
+    })
+    .await
+}
+
+/// PUT /{table}?filters: Sets the body's columns on every row matching the
+/// filters. At least one filter is required.
+async fn handle_bulk_put(
+    State(table): State<Arc<TableState>>,
+    params: QueryParams,
+    headers: axum::http::HeaderMap,
+    JsonBody(mut payload): JsonBody,
+) -> (StatusCode, Json<Value>) {
+    blocking(move || {
+        if let Err(e) = confirm_bulk(&headers) {
+            return e;
+        }
+        if !has_filters(&params) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Bulk updates require at least one filter"})),
+            );
+        }
+        apply_timestamps(&table, &mut payload, &["updated_at"]);
+        let obj = match payload.as_object() {
+            Some(obj) if !obj.is_empty() => obj,
+            _ => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": "Invalid JSON format"})),
+                )
+            }
+        };
+        for key in obj.keys() {
+            if !is_valid_identifier(key) {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": "Invalid column name"})),
+                );
+            }
+            if let Err(e) = known_column(&table, key) {
+                return e;
+            }
+        }
+        if let Err(e) = validate_row(&table.config, obj, false) {
+            return e;
+        }
+        let (filters, filter_params) = match filter_conditions(&table, &params) {
+            Ok(f) => f,
+            Err(e) => return e,
+        };
+        let values = match body_values(&table.config, obj) {
+            Ok(v) => v,
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": e.to_string()})),
+                )
+            }
+        };
+
+        let updates: Vec<String> = obj
+            .keys()
+            .map(|k| format!("{} = ?", quote_ident(k)))
+            .collect();
+        let sql = format!(
+            "UPDATE {} SET {} WHERE {}",
+            quote_ident(&table.name),
+            updates.join(", "),
+            filters.join(" AND ")
+        );
+        let mut sql_params: Vec<&dyn ToSql> = values.iter().map(|v| v as &dyn ToSql).collect();
+        sql_params.extend(filter_params.iter().map(|p| p.as_ref()));
+
+        let conn = lock_conn(&table.conn);
+        match retry_busy(table.busy_retry, || {
+            conn.execute(&sql, sql_params.as_slice())
+        }) {
+            Ok(updated) => (
+                StatusCode::OK,
+                Json(serde_json::json!({"status": "success", "updated": updated, "affected": updated})),
+            ),
+            Err(e) => write_error(e),
+        }
+    })
+    .await
+}
+
+/// Helper: Runs synchronous SQLite work on tokio's blocking pool, so a slow
+/// query or a wait for the connection lock never stalls the async workers.
+/// A panic inside `work` resumes on the caller, as if it had run inline.
+async fn blocking<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> T {
+    match tokio::task::spawn_blocking(work).await {
+        Ok(result) => result,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+/// Helper: Locks a connection, recovering it if a panicking request left the
+/// mutex poisoned. The connection itself is still usable (an open transaction
+/// is rolled back when its guard drops), so one bad request can't take every
+/// later one down with it.
+fn lock_conn(conn: &Mutex<Connection>) -> std::sync::MutexGuard<'_, Connection> {
+    conn.lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Helper: Runs a write, retrying it while SQLite reports the database as busy
+fn retry_busy<T>(
+    policy: BusyRetry,
+    mut op: impl FnMut() -> rusqlite::Result<T>,
+) -> rusqlite::Result<T> {
+    let mut delay = policy.backoff;
+    let mut attempt = 0;
+    loop {
+        match op() {
+            Err(rusqlite::Error::SqliteFailure(err, _))
+                if err.code == rusqlite::ErrorCode::DatabaseBusy && attempt < policy.retries =>
+            {
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Helper: Maps a failed write to a response. Constraint violations are client
+/// errors (duplicate -> 409, broken foreign key -> 400), anything else is a 500.
+fn write_error(e: rusqlite::Error) -> (StatusCode, Json<Value>) {
+    use rusqlite::ffi;
+
+    if let rusqlite::Error::SqliteFailure(err, msg) = &e {
+        match err.extended_code {
+            ffi::SQLITE_CONSTRAINT_UNIQUE | ffi::SQLITE_CONSTRAINT_PRIMARYKEY => {
+                // Message format: "UNIQUE constraint failed: students.school_number"
+                let column = msg
+                    .as_deref()
+                    .and_then(|m| m.split_once(": "))
+                    .map(|(_, cols)| {
+                        cols.split(", ")
+                            .map(|c| c.rsplit('.').next().unwrap_or(c))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    });
+                return (
+                    StatusCode::CONFLICT,
+                    Json(serde_json::json!({"error": "duplicate", "column": column})),
+                );
+            }
+            ffi::SQLITE_CONSTRAINT_FOREIGNKEY => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({"error": "foreign key constraint failed"})),
+                );
+            }
+            _ if err.code == rusqlite::ErrorCode::DatabaseBusy => {
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(serde_json::json!({"error": "Database is busy"})),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(serde_json::json!({"error": e.to_string()})),
+    )
+}
+
+/// Helper: Resolves the tables named in `_expand` (parents) and `_embed` (children).
+fn requested_relations<'a>(
+    table: &'a TableState,
+    params: &HashMap<String, String>,
+) -> Result<(Vec<&'a RelatedTable>, Vec<&'a RelatedTable>), String> {
+    let lookup = |key: &str, candidates: &'a [RelatedTable], what: &str| {
+        let mut found = Vec::new();
+        if let Some(names) = params.get(key) {
+            for name in names.split(',').map(|n| n.trim()) {
+                match candidates.iter().find(|c| c.name == name) {
+                    Some(related) => found.push(related),
+                    None => return Err(format!("No relation to {}: {}", what, name)),
+                }
+            }
+        }
+        Ok(found)
+    };
+
+    Ok((
+        lookup("_expand", &table.parents, "expand")?,
+        lookup("_embed", &table.children, "embed")?,
+    ))
+}
+
+/// Helper: Applies the requested expansions and embeddings to the rows.
+fn attach_relations(
+    expand: &[&RelatedTable],
+    embed: &[&RelatedTable],
+    rows: &mut [Value],
+    scopes: &Scopes,
+) -> rusqlite::Result<()> {
+    for parent in expand {
+        expand_parent(parent, rows, scopes)?;
+    }
+    for child in embed {
+        embed_children(child, rows, scopes)?;
+    }
+    Ok(())
+}
+
+/// Helper: Nests the child rows that reference each row under the child's name.
+fn embed_children(
+    child: &RelatedTable,
+    rows: &mut [Value],
+    scopes: &Scopes,
+) -> rusqlite::Result<()> {
+    let conn = lock_conn(&child.conn);
+    let sql = format!(
+        "SELECT * FROM {} WHERE {} = ?",
+        quote_ident(&child.name),
+        quote_ident(&child.column)
+    );
+    let mut stmt = conn.prepare(&sql)?;
+
+    for row in rows.iter_mut() {
+        let key = row.get(&child.column).cloned().unwrap_or(Value::Null);
+        let found = if key.is_null() {
+            Vec::new()
+        } else {
+            stmt.query_map([json_to_sql(&key)], |r| {
+                Ok(row_to_json(r, &child.config, scopes))
+            })?
+            .collect::<rusqlite::Result<Vec<Value>>>()?
+        };
+        if let Some(obj) = row.as_object_mut() {
+            obj.insert(child.name.clone(), Value::from(found));
+        }
+    }
+    Ok(())
+}
+
+/// Helper: Nests the parent row referenced by each row under the parent's name.
+/// Rows whose parent doesn't exist (orphaned references) get an explicit `null`.
+fn expand_parent(
+    parent: &RelatedTable,
+    rows: &mut [Value],
+    scopes: &Scopes,
+) -> rusqlite::Result<()> {
+    let conn = lock_conn(&parent.conn);
+    let sql = format!(
+        "SELECT * FROM {} WHERE {} = ? LIMIT 1",
+        quote_ident(&parent.name),
+        quote_ident(&parent.column)
+    );
+    let mut stmt = conn.prepare(&sql)?;
+
+    for row in rows.iter_mut() {
+        let key = row.get(&parent.column).cloned().unwrap_or(Value::Null);
+        let found = if key.is_null() {
+            None
+        } else {
+            let mut matches = stmt.query_map([json_to_sql(&key)], |r| {
+                Ok(row_to_json(r, &parent.config, scopes))
+            })?;
+            matches.next().transpose()?
+        };
+        if let Some(obj) = row.as_object_mut() {
+            obj.insert(parent.name.clone(), found.unwrap_or(Value::Null));
+        }
+    }
+    Ok(())
+}
+
+/// Helper: Resolves the LIMIT/OFFSET pair from the query parameters.
+///
+/// `_limit`/`_offset` take precedence; `_page`/`_per_page` (offset =
+/// (page - 1) * per_page, per_page defaults to 10) are only used when
+/// neither of them is present.
+fn pagination(params: &HashMap<String, String>) -> Result<Option<(i64, i64)>, String> {
+    let number = |key: &str, min: i64| -> Result<Option<i64>, String> {
+        match params.get(key) {
+            Some(v) => match v.parse::<i64>() {
+                Ok(n) if n >= min => Ok(Some(n)),
+                _ => Err(format!("Invalid {}: {}", key, v)),
+            },
+            None => Ok(None),
+        }
+    };
+
+    let limit = number("_limit", 0)?;
+    let offset = number("_offset", 0)?;
+    if limit.is_some() || offset.is_some() {
+        // SQLite needs a LIMIT for OFFSET; -1 means "no limit"
+        return Ok(Some((limit.unwrap_or(-1), offset.unwrap_or(0))));
+    }
+
+    let page = number("_page", 1)?;
+    let per_page = number("_per_page", 1)?;
+    if page.is_none() && per_page.is_none() {
+        return Ok(None);
+    }
+    let per_page = per_page.unwrap_or(10);
+    Ok(Some((per_page, (page.unwrap_or(1) - 1) * per_page)))
+}
+
+/// Helper: Converts SQLite row to JSON (hidden columns are skipped)
+fn row_to_json(row: &rusqlite::Row, config: &TableConfig, scopes: &Scopes) -> Value {
+    let mut map = Map::new();
+    let column_names = row.as_ref().column_names();
+
+    for (i, name) in column_names.iter().enumerate() {
+        if config.hidden_columns.contains(*name) {
+            continue;
+        }
+        let value = read_value(config, name, value_to_json(row.get_ref(i).unwrap()));
+        map.insert(name.to_string(), mask_value(config, scopes, name, value));
+    }
+    Value::Object(map)
+}
+
+/// Helper: Like `row_to_json`, but each value becomes `{"value": ..., "type": ...}`
+/// with the SQLite storage class it was read as (INTEGER, REAL, TEXT, BLOB or NULL)
+fn row_to_typed_json(row: &rusqlite::Row, config: &TableConfig, scopes: &Scopes) -> Value {
+    let mut json = row_to_json(row, config, scopes);
+    for (i, name) in row.as_ref().column_names().iter().enumerate() {
+        let Some(value) = json.get_mut(*name) else {
+            continue; // hidden
+        };
+        let kind = match row.get_ref(i) {
+            Ok(ValueRef::Integer(_)) => "INTEGER",
+            Ok(ValueRef::Real(_)) => "REAL",
+            Ok(ValueRef::Text(_)) => "TEXT",
+            Ok(ValueRef::Blob(_)) => "BLOB",
+            Ok(ValueRef::Null) | Err(_) => "NULL",
+        };
+        *value = serde_json::json!({"value": value.take(), "type": kind});
+    }
+    json
+}
+
+/// Helper: Whether the request asked for typed values (`?_typed=true`)
+fn wants_typed(params: &HashMap<String, String>) -> bool {
+    params.get("_typed").is_some_and(|v| v == "true")
+}
+
+/// Helper: Converts a stored value to the JSON its column kind calls for.
+/// Text that isn't valid JSON is returned as the plain string.
+fn read_value(config: &TableConfig, column: &str, value: Value) -> Value {
+    match (config.kinds.get(column), value) {
+        (Some(ColumnKind::Json), Value::String(text)) => {
+            serde_json::from_str(&text).unwrap_or(Value::String(text))
+        }
+        (Some(ColumnKind::Bool), Value::Number(n)) if n.is_i64() => {
+            Value::Bool(n.as_i64() != Some(0))
+        }
+        (_, value) => value,
+    }
+}
+
+/// Helper: Converts a written JSON value to what its column kind stores
+fn stored_value(config: &TableConfig, column: &str, value: &Value) -> Value {
+    match config.kinds.get(column) {
+        Some(ColumnKind::Json) if !value.is_null() => Value::String(value.to_string()),
+        Some(ColumnKind::Bool) => match value {
+            Value::Bool(b) => Value::from(*b as i64),
+            other => other.clone(),
+        },
+        Some(ColumnKind::DateTime) => match value.as_str().and_then(normalize_rfc3339) {
+            Some(normalized) => Value::String(normalized),
+            None => value.clone(),
+        },
+        _ => value.clone(),
+    }
+}
+
+/// Helper: Applies the column's mask unless the request has the scope to see it
+fn mask_value(config: &TableConfig, scopes: &Scopes, column: &str, value: Value) -> Value {
+    match config.masks.get(column) {
+        Some(rule) if !scopes.0.contains(&rule.scope) => (rule.mask)(&value),
+        _ => value,
+    }
+}
+
+/// Helper: Whether a filter or sort on this column must be refused (see `with_strict_hidden_columns`)
+fn is_hidden_for_queries(table: &TableState, column: &str) -> bool {
+    table.strict_hidden_columns && table.config.hidden_columns.contains(column)
+}
+
+/// Helper: Converts JSON body values into bind parameters, hashing secret columns
+/// (JSON `null` is bound as SQL NULL, and never hashed)
+fn body_values(
+    config: &TableConfig,
+    obj: &Map<String, Value>,
+) -> anyhow::Result<Vec<rusqlite::types::Value>> {
+    obj.iter()
+        .map(|(k, v)| {
+            let v = stored_value(config, k, v);
+            if config.hashed_columns.contains(k) && !v.is_null() {
+                let text = v.as_str().map(str::to_string).unwrap_or(v.to_string());
+                Ok(rusqlite::types::Value::Text(hash_secret(&text)?))
+            } else {
+                Ok(json_to_sql(&v))
+            }
+        })
+        .collect()
+}
+
+/// Helper: Sets the timestamp columns to the current time when the table uses `auto_timestamps`
+fn apply_timestamps(table: &TableState, payload: &mut Value, columns: &[&str]) {
+    if let Some(obj) = payload.as_object_mut() {
+        stamp_row(table, obj, columns);
+    }
+}
+
+/// Helper: Same as `apply_timestamps`, for a row that is already an object
+fn stamp_row(table: &TableState, obj: &mut Map<String, Value>, columns: &[&str]) {
+    if !table.config.timestamps {
+        return;
+    }
+    let now = now_rfc3339(table.clock.as_ref());
+    for col in columns {
+        obj.insert(col.to_string(), Value::String(now.clone()));
+    }
+}
+
+/// Helper: The clock's current time as an RFC3339 UTC string with milliseconds
+fn now_rfc3339(clock: &dyn Clock) -> String {
+    chrono::DateTime::<chrono::Utc>::from(clock.now())
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// Helper: Parses an RFC3339 timestamp and formats it in UTC like `now_rfc3339`
+fn normalize_rfc3339(text: &str) -> Option<String> {
+    let parsed = chrono::DateTime::parse_from_rfc3339(text).ok()?;
+    Some(
+        parsed
+            .with_timezone(&chrono::Utc)
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+    )
+}
+
+/// Helper: Whether reads must skip soft-deleted rows (`?_with_deleted=true` includes them)
+fn hides_deleted(table: &TableState, params: &HashMap<String, String>) -> bool {
+    table.config.soft_delete && params.get("_with_deleted").map(|v| v.as_str()) != Some("true")
+}
+
+/// Helper: Hashes a secret with Argon2 and a random salt (PHC string format)
+#[cfg(feature = "hashing")]
+fn hash_secret(plain: &str) -> anyhow::Result<String> {
+    use argon2::password_hash::{rand_core::OsRng, PasswordHasher, SaltString};
+
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = argon2::Argon2::default()
+        .hash_password(plain.as_bytes(), &salt)
+        .map_err(|e| anyhow::anyhow!("Failed to hash value: {}", e))?;
+    Ok(hash.to_string())
+}
+
+#[cfg(not(feature = "hashing"))]
+fn hash_secret(_plain: &str) -> anyhow::Result<String> {
+    Err(anyhow::anyhow!(
+        "Column hashing requires the 'hashing' feature"
+    ))
+}
+
+/// Helper: Converts a JSON value into a typed SQLite value
+fn json_to_sql(value: &Value) -> rusqlite::types::Value {
+    use rusqlite::types::Value as SqlValue;
+
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+/// Helper: Converts a single SQLite value to JSON
+fn value_to_json(value: ValueRef) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(n) => Value::from(n),
+        ValueRef::Real(f) => Value::from(f),
+        ValueRef::Text(t) => Value::from(std::str::from_utf8(t).unwrap_or("")),
+        ValueRef::Blob(b) => Value::from(format!("{:?}", b)),
+    }
+}
@@ -1,12 +1,31 @@
-use easy_db::{EasyClient, EasyDB};
+use easy_db::{Clock, ColumnKind, DbConfig, EasyClient, EasyDB, OpenFlags, Order, Query};
 use serde_json::json;
 use std::collections::HashMap;
 use std::time::Duration;
 use tokio::time::sleep;
 
-/// Helper: Starts a test server in the background for integration testing.
-async fn start_test_server(port: u16, db_name: &str) {
-    let mut db = EasyDB::init(db_name).expect("Failed to init DB");
+/// Helper: Serves `db` on a free port in the background and returns the
+/// port. The listener is bound before this returns, so no startup wait is needed.
+async fn serve(db: EasyDB) -> u16 {
+    let server = db.bind(0).await.expect("Failed to bind");
+    let port = server.local_addr().port();
+    tokio::spawn(server.serve());
+    port
+}
+
+/// Helper: Creates an empty directory for the database files of test `name`
+/// in the system temp directory, so file-backed tests leave nothing in the
+/// working tree. Remove it at the end of the test.
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("easy_db_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("Failed to create temp dir");
+    dir
+}
+
+/// Helper: Starts a test server with `students` and `logs` tables and returns its port.
+async fn start_test_server(db_name: &str) -> u16 {
+    let mut db = EasyDB::init_in_memory(db_name).expect("Failed to init DB");
 
     // Create test tables
     db.create_table(
@@ -18,19 +37,13 @@ async fn start_test_server(port: u16, db_name: &str) {
     db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
         .expect("Failed to create logs table");
 
-    tokio::spawn(async move {
-        let _ = db.run_server(port).await;
-    });
-
-    // Wait for the server to be ready
-    sleep(Duration::from_millis(300)).await;
+    serve(db).await
 }
 
 #[tokio::test]
 async fn test_professional_crud_flow() {
-    let port = 9600;
     let db_name = "test_db";
-    start_test_server(port, db_name).await;
+    let port = start_test_server(db_name).await;
 
     let client = EasyClient::new("localhost", port);
 
@@ -109,3 +122,4441 @@ async fn test_professional_crud_flow() {
 
     println!("🚀 All professional test scenarios (CRUD + Sort + Error) passed successfully!");
 }
+
+#[tokio::test]
+async fn test_table_mapped_to_separate_db() {
+    let dir = temp_dir("shard");
+    let (main_path, archive_path) = (dir.join("main.db"), dir.join("archive.db"));
+
+    let mut db = EasyDB::init_at(&main_path).expect("Failed to init DB");
+    db.map_table_to_db("archive", archive_path.to_str().unwrap())
+        .expect("Failed to map table");
+    db.create_table("archive", "id INTEGER PRIMARY KEY, note TEXT")
+        .expect("Failed to create archive table");
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    let res = client
+        .post("archive", json!({"note": "old record"}))
+        .await
+        .expect("POST failed");
+    assert_eq!(res["status"], "success");
+
+    let rows = client.get("archive", None).await.expect("GET failed");
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+
+    // The row must live in the mapped file, not in the main database
+    let archive = rusqlite::Connection::open(&archive_path).unwrap();
+    let count: i64 = archive
+        .query_row("SELECT COUNT(*) FROM archive", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(count, 1);
+
+    let main = rusqlite::Connection::open(&main_path).unwrap();
+    let exists: i64 = main
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE name = 'archive'",
+            [],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert_eq!(exists, 0);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_upsert_by_unique_column() {
+    let mut db = EasyDB::init_in_memory("test_upsert_db").expect("Failed to init DB");
+    db.create_table(
+        "students",
+        "id INTEGER PRIMARY KEY, name TEXT, school_number INTEGER UNIQUE, gpa REAL, \
+         email TEXT, city TEXT NOT NULL DEFAULT 'Ankara'",
+    )
+    .expect("Failed to create students table");
+    db.with_connection(|conn| {
+        conn.execute_batch("CREATE UNIQUE INDEX students_email ON students (email)")
+    })
+    .unwrap();
+    db.create_table(
+        "badges",
+        "id INTEGER PRIMARY KEY, code TEXT UNIQUE, label TEXT NOT NULL",
+    )
+    .unwrap();
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+
+    // First sync creates the row, second sync updates it in place
+    let student = json!({"name": "Ali", "school_number": 101, "gpa": 85.5});
+    let res = client
+        .upsert("students", "school_number", student)
+        .await
+        .expect("UPSERT failed");
+    assert_eq!(res["status"], "success");
+
+    let student = json!({"name": "Ali Yilmaz", "school_number": 101, "gpa": 90.0});
+    client
+        .upsert("students", "school_number", student)
+        .await
+        .expect("UPSERT failed");
+
+    let rows = client.get("students", None).await.expect("GET failed");
+    let list = rows.as_array().unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0]["name"], "Ali Yilmaz");
+    assert_eq!(list[0]["gpa"], 90.0);
+
+    // A column without a UNIQUE constraint cannot be used as the conflict target
+    let res = client
+        .upsert("students", "name", json!({"name": "Ali Yilmaz"}))
+        .await
+        .expect("UPSERT failed");
+    assert!(res["error"].as_str().unwrap().contains("UNIQUE"));
+
+    // A UNIQUE index and the INTEGER PRIMARY KEY are conflict targets too
+    let res = client
+        .upsert(
+            "students",
+            "email",
+            json!({"email": "ali@example.com", "name": "Ali"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["status"], "success");
+    let res = client
+        .upsert("students", "id", json!({"id": 1, "gpa": 95.0}))
+        .await
+        .unwrap();
+    assert_eq!(res["status"], "success");
+
+    // A row it may insert needs the same required columns as a POST
+    let res = client
+        .upsert("badges", "code", json!({"code": "gold"}))
+        .await
+        .unwrap();
+    assert_eq!(res["errors"]["label"], "required");
+}
+
+#[tokio::test]
+async fn test_facet_counts() {
+    let mut db = EasyDB::init_in_memory("test_facets_db").expect("Failed to init DB");
+    db.create_table(
+        "students",
+        "id INTEGER PRIMARY KEY, name TEXT, class_grade TEXT",
+    )
+    .expect("Failed to create students table");
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    for (name, grade) in [("Ali", "10-A"), ("Zeynep", "10-A"), ("Mehmet", "11-B")] {
+        client
+            .post("students", json!({"name": name, "class_grade": grade}))
+            .await
+            .expect("POST failed");
+    }
+
+    let mut params = HashMap::new();
+    params.insert("columns", "class_grade");
+    let facets = client
+        .get("students/facets", Some(params))
+        .await
+        .expect("GET facets failed");
+
+    let grades = facets["class_grade"].as_array().expect("Missing facet");
+    assert_eq!(grades.len(), 2);
+    assert_eq!(grades[0], json!({"value": "10-A", "count": 2}));
+    assert_eq!(grades[1], json!({"value": "11-B", "count": 1}));
+}
+
+#[test]
+fn test_in_memory_db_leaves_no_file() {
+    let mut db = EasyDB::init_in_memory("test_memory_db").expect("Failed to init DB");
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+
+    assert_eq!(db.db_name, "test_memory_db");
+    assert!(!std::path::Path::new("test_memory_db.db").exists());
+}
+
+/// Helper: Opens a raw connection and sends only the headers of a POST,
+/// leaving the request in flight until the body is written.
+async fn start_slow_post(port: u16, body: &str) -> tokio::net::TcpStream {
+    use tokio::io::AsyncWriteExt;
+
+    let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port))
+        .await
+        .expect("Failed to connect");
+    let head = format!(
+        "POST /logs HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await.unwrap();
+    stream
+}
+
+#[tokio::test]
+async fn test_graceful_shutdown_drains_in_flight_requests() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut db = EasyDB::init_in_memory("test_drain_db")
+        .expect("Failed to init DB")
+        .with_drain_timeout(Duration::from_secs(2));
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+
+    let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+    let server = db.bind(0).await.unwrap();
+    let port = server.local_addr().port();
+    let server = tokio::spawn(server.serve_with_shutdown(async {
+        let _ = stop_rx.await;
+    }));
+
+    let body = r#"{"message": "slow"}"#;
+    let mut stream = start_slow_post(port, body).await;
+    sleep(Duration::from_millis(100)).await;
+
+    // Trigger shutdown while the request body is still outstanding
+    stop_tx.send(()).unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    stream.write_all(body.as_bytes()).await.unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await.unwrap();
+    let response = String::from_utf8_lossy(&response);
+    assert!(response.starts_with("HTTP/1.1 201"), "got: {}", response);
+
+    // The server stops once the drained connection is done
+    let res = tokio::time::timeout(Duration::from_secs(1), server)
+        .await
+        .expect("Server did not stop");
+    assert!(res.unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn test_graceful_shutdown_closes_after_drain_timeout() {
+    use tokio::io::AsyncReadExt;
+
+    let mut db = EasyDB::init_in_memory("test_drain_timeout_db")
+        .expect("Failed to init DB")
+        .with_drain_timeout(Duration::from_millis(200));
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+
+    let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+    let server = db.bind(0).await.unwrap();
+    let port = server.local_addr().port();
+    let server = tokio::spawn(server.serve_with_shutdown(async {
+        let _ = stop_rx.await;
+    }));
+
+    // This request never sends its body, so it can only end by force
+    let mut stream = start_slow_post(port, r#"{"message": "stuck"}"#).await;
+    sleep(Duration::from_millis(100)).await;
+    stop_tx.send(()).unwrap();
+
+    let res = tokio::time::timeout(Duration::from_secs(1), server)
+        .await
+        .expect("Server ignored the drain timeout");
+    assert!(res.unwrap().is_ok());
+
+    let mut buf = Vec::new();
+    let read = tokio::time::timeout(Duration::from_secs(1), stream.read_to_end(&mut buf))
+        .await
+        .expect("Connection was not closed");
+    assert!(read.is_err() || buf.is_empty());
+}
+
+#[tokio::test]
+async fn test_hidden_column_is_never_returned() {
+    let mut db = EasyDB::init_in_memory("test_hidden_db").expect("Failed to init DB");
+    db.create_table(
+        "users",
+        "id INTEGER PRIMARY KEY, name TEXT, internal_note TEXT",
+    )
+    .expect("Failed to create users table");
+    db.hide_column("users", "internal_note")
+        .expect("Failed to hide column");
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    client
+        .post("users", json!({"name": "Ali", "internal_note": "vip"}))
+        .await
+        .expect("POST failed");
+
+    let rows = client.get("users", None).await.expect("GET failed");
+    let user = &rows.as_array().unwrap()[0];
+    assert_eq!(user["name"], "Ali");
+    assert!(user.get("internal_note").is_none());
+
+    // Facets would reveal the hidden values, so they are refused
+    let mut params = HashMap::new();
+    params.insert("columns", "internal_note");
+    let res = client.get("users/facets", Some(params)).await.unwrap();
+    assert!(res["error"].is_string());
+}
+
+#[cfg(feature = "hashing")]
+#[tokio::test]
+async fn test_hashed_column_is_stored_hashed() {
+    let dir = temp_dir("hash");
+    let path = dir.join("users.db");
+
+    let mut db = EasyDB::init_at(&path).expect("Failed to init DB");
+    db.create_table("users", "id INTEGER PRIMARY KEY, name TEXT, password TEXT")
+        .expect("Failed to create users table");
+    db.hash_column("users", "password")
+        .expect("Failed to hash column");
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    client
+        .post("users", json!({"name": "Ali", "password": "hunter2"}))
+        .await
+        .expect("POST failed");
+
+    let rows = client.get("users", None).await.expect("GET failed");
+    let user = &rows.as_array().unwrap()[0];
+    assert!(user.get("password").is_none());
+
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    let stored: String = conn
+        .query_row("SELECT password FROM users WHERE name = 'Ali'", [], |r| {
+            r.get(0)
+        })
+        .unwrap();
+    assert_ne!(stored, "hunter2");
+    assert!(stored.starts_with("$argon2"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_read_only_database_rejects_writes() {
+    let dir = temp_dir("readonly");
+    let path = dir.join("students.db");
+
+    // Another process maintains the data...
+    let mut writer = EasyDB::init_at(&path).expect("Failed to init DB");
+    writer
+        .create_table("students", "id INTEGER PRIMARY KEY, name TEXT")
+        .expect("Failed to create students table");
+    drop(writer);
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .execute("INSERT INTO students (name) VALUES ('Ali')", [])
+        .unwrap();
+
+    // ...and this replica only serves it
+    let mut db = EasyDB::builder("students")
+        .path(&path)
+        .flags(OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .build()
+        .expect("Failed to open read-only DB");
+    db.expose_table("students").expect("Failed to expose table");
+    assert!(db.expose_table("missing").is_err());
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    let rows = client.get("students", None).await.expect("GET failed");
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+
+    let http = reqwest::Client::new();
+    let base = format!("http://localhost:{}", port);
+    let res = http
+        .post(format!("{}/students", base))
+        .json(&json!({"name": "Zeynep"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::METHOD_NOT_ALLOWED);
+
+    let res = http
+        .delete(format!("{}/students/1", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::METHOD_NOT_ALLOWED);
+
+    let res = http
+        .request(reqwest::Method::OPTIONS, format!("{}/students/1", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.headers()["allow"], "GET, HEAD, OPTIONS");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_read_only_database_maps_tables_read_only() {
+    let dir = temp_dir("readonly_map");
+    let (path, archive_path) = (dir.join("main.db"), dir.join("archive.db"));
+    EasyDB::init_at(&path).expect("Failed to init DB");
+    rusqlite::Connection::open(&archive_path)
+        .unwrap()
+        .execute_batch(
+            "CREATE TABLE archive (id INTEGER PRIMARY KEY, note TEXT);
+             INSERT INTO archive (note) VALUES ('old');",
+        )
+        .unwrap();
+
+    let mut db = EasyDB::builder("main")
+        .path(&path)
+        .flags(OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .build()
+        .expect("Failed to open read-only DB");
+    db.map_table_to_db("archive", archive_path.to_str().unwrap())
+        .unwrap();
+    db.expose_table("archive").unwrap();
+    // Nothing writes to the mapped file, not even the Rust API
+    assert!(db.insert("archive", &json!({"note": "new"})).is_err());
+    db.reopen().unwrap();
+    assert!(db.insert("archive", &json!({"note": "new"})).is_err());
+
+    let port = serve(db).await;
+    let rows = EasyClient::new("localhost", port)
+        .get("archive", None)
+        .await
+        .unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_wal_mode_with_concurrent_writes_and_reads() {
+    let dir = temp_dir("wal");
+    let path = dir.join("logs.db");
+
+    let config = DbConfig {
+        wal: true,
+        busy_timeout_ms: 2000,
+        ..Default::default()
+    };
+    let mut db = EasyDB::builder("logs")
+        .path(&path)
+        .config(config)
+        .build()
+        .expect("Failed to init DB");
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+
+    let port = serve(db).await;
+
+    // Journal mode is persisted in the file, so any connection can see it
+    let mode: String = rusqlite::Connection::open(&path)
+        .unwrap()
+        .query_row("PRAGMA journal_mode", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(mode, "wal");
+
+    let mut tasks = Vec::new();
+    for i in 0..20 {
+        tasks.push(tokio::spawn(async move {
+            let client = EasyClient::new("localhost", port);
+            let res = client
+                .post("logs", json!({"message": format!("entry {}", i)}))
+                .await
+                .expect("POST failed");
+            assert_eq!(res["status"], "success");
+            client.get("logs", None).await.expect("GET failed");
+        }));
+    }
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    let client = EasyClient::new("localhost", port);
+    let rows = client.get("logs", None).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 20);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_pagination_with_page_and_per_page() {
+    let port = start_test_server("test_pagination_db").await;
+    let client = EasyClient::new("localhost", port);
+
+    for i in 1..=5 {
+        client
+            .post("logs", json!({"message": format!("entry {}", i)}))
+            .await
+            .expect("POST failed");
+    }
+
+    let mut params = HashMap::new();
+    params.insert("_sort", "id");
+    params.insert("_page", "2");
+    params.insert("_per_page", "2");
+    let page = client.get("logs", Some(params)).await.expect("GET failed");
+    let list = page.as_array().unwrap();
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0]["message"], "entry 3");
+    assert_eq!(list[1]["message"], "entry 4");
+
+    // `_limit`/`_offset` win over `_page`/`_per_page`
+    let mut params = HashMap::new();
+    params.insert("_sort", "id");
+    params.insert("_limit", "1");
+    params.insert("_offset", "4");
+    params.insert("_page", "1");
+    params.insert("_per_page", "2");
+    let page = client.get("logs", Some(params)).await.expect("GET failed");
+    let list = page.as_array().unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0]["message"], "entry 5");
+}
+
+#[tokio::test]
+async fn test_expand_orphaned_parent_is_null() {
+    let mut db = EasyDB::init_in_memory("test_expand_db").expect("Failed to init DB");
+    db.create_table(
+        "students",
+        "id INTEGER PRIMARY KEY, name TEXT, school_number INTEGER UNIQUE",
+    )
+    .expect("Failed to create students table");
+    db.create_table(
+        "grades",
+        "id INTEGER PRIMARY KEY, school_number INTEGER, lesson TEXT, score INTEGER",
+    )
+    .expect("Failed to create grades table");
+    db.add_relation("students", "grades", "school_number")
+        .expect("Failed to add relation");
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    client
+        .post("students", json!({"name": "Ali", "school_number": 101}))
+        .await
+        .unwrap();
+    client
+        .post(
+            "grades",
+            json!({"school_number": 101, "lesson": "Mathematics", "score": 90}),
+        )
+        .await
+        .unwrap();
+    // This student was never registered
+    client
+        .post(
+            "grades",
+            json!({"school_number": 999, "lesson": "Physics", "score": 70}),
+        )
+        .await
+        .unwrap();
+
+    let mut params = HashMap::new();
+    params.insert("_expand", "students");
+    params.insert("_sort", "id");
+    let rows = client
+        .get("grades", Some(params))
+        .await
+        .expect("GET failed");
+    let list = rows.as_array().unwrap();
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0]["students"]["name"], "Ali");
+
+    // The key is present and explicitly null for the orphan
+    let orphan = list[1].as_object().unwrap();
+    assert!(orphan.contains_key("students"));
+    assert!(orphan["students"].is_null());
+
+    let mut params = HashMap::new();
+    params.insert("_expand", "teachers");
+    let res = client.get("grades", Some(params)).await.unwrap();
+    assert!(res["error"].is_string());
+}
+
+#[tokio::test]
+async fn test_duplicate_unique_value_returns_conflict() {
+    let mut db = EasyDB::init_in_memory("test_conflict_db").expect("Failed to init DB");
+    db.create_table(
+        "students",
+        "id INTEGER PRIMARY KEY, name TEXT, school_number INTEGER UNIQUE",
+    )
+    .expect("Failed to create students table");
+
+    let port = serve(db).await;
+
+    let http = reqwest::Client::new();
+    let url = format!("http://localhost:{}/students", port);
+    let res = http
+        .post(&url)
+        .json(&json!({"name": "Ali", "school_number": 101}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::CREATED);
+
+    let res = http
+        .post(&url)
+        .json(&json!({"name": "Zeynep", "school_number": 101}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::CONFLICT);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(
+        body,
+        json!({"error": "duplicate", "column": "school_number"})
+    );
+}
+
+#[test]
+fn test_strict_identifiers_reject_non_snake_case() {
+    let mut db = EasyDB::init_in_memory("test_strict_db")
+        .expect("Failed to init DB")
+        .with_strict_identifiers(true);
+
+    assert!(db
+        .create_table("camelCase", "id INTEGER PRIMARY KEY")
+        .is_err());
+    assert!(db
+        .create_table("students", "id INTEGER PRIMARY KEY, bad__name TEXT")
+        .is_err());
+    assert!(db
+        .create_table("students", "id INTEGER PRIMARY KEY, firstName TEXT")
+        .is_err());
+    assert!(db
+        .create_table(
+            "grades",
+            "id INTEGER PRIMARY KEY, school_number INTEGER, score REAL, UNIQUE (school_number, score)",
+        )
+        .is_ok());
+
+    // Without strict mode the same names are accepted
+    let mut relaxed = EasyDB::init_in_memory("test_relaxed_db").expect("Failed to init DB");
+    assert!(relaxed
+        .create_table("camelCase", "id INTEGER PRIMARY KEY, bad__name TEXT")
+        .is_ok());
+}
+
+#[test]
+fn test_create_table_validates_column_definitions() {
+    let mut db = EasyDB::init_in_memory("test_column_defs_db")
+        .unwrap()
+        .with_strict_column_types(true);
+
+    for columns in [
+        "id INTEGER PRIMARY KEY); DROP TABLE users; --",
+        "id INTEGER PRIMARY KEY, name TEXT) --",
+        "id INTEGER PRIMARY KEY /* note */",
+        "id INTEGER PRIMARY KEY, CHECK (id > 0",
+        "id INTEGER PRIMARY KEY, name TEXT DEFAULT 'x",
+        "id INTEGER PRIMARY KEY, age INTEGR",
+        "id INTEGER PRIMARY KEY, na-me TEXT",
+        "",
+    ] {
+        let err = db.create_table("students", columns).unwrap_err();
+        assert!(!err.to_string().is_empty(), "{:?}", columns);
+    }
+    let err = db
+        .create_table("students", "id INTEGER PRIMARY KEY, age INTEGR")
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Unknown type for column 'age': INTEGR");
+
+    // Types with sizes, untyped columns, and constraints or quotes holding ';' are fine
+    db.create_table(
+        "students",
+        "id INTEGER PRIMARY KEY, name VARCHAR(40) NOT NULL DEFAULT 'a;b', note, \
+         \"group\" TEXT, gpa REAL CHECK (gpa >= 0), UNIQUE (name, \"group\")",
+    )
+    .unwrap();
+    db.create_table("profiles", "id UUID PRIMARY KEY, settings JSON")
+        .unwrap();
+
+    // Without strict types an unknown type name is only warned about
+    let mut relaxed = EasyDB::init_in_memory("test_column_types_db").unwrap();
+    relaxed
+        .create_table(
+            "places",
+            "id INTEGER PRIMARY KEY, shape GEOMETRY, age INTEGR",
+        )
+        .unwrap();
+    for columns in [
+        "id INTEGER PRIMARY KEY, age INT-EGER",
+        "id INTEGER PRIMARY KEY); DROP TABLE users; --",
+    ] {
+        assert!(
+            relaxed.create_table("others", columns).is_err(),
+            "{:?}",
+            columns
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_get_many_keeps_requested_order() {
+    let port = start_test_server("test_get_many_db").await;
+    let client = EasyClient::new("localhost", port);
+
+    for name in ["Ali", "Zeynep", "Mehmet", "Ayse"] {
+        client
+            .post("students", json!({"name": name, "age": 20, "gpa": 3.0}))
+            .await
+            .expect("POST failed");
+    }
+
+    let rows = client
+        .get_many("students", &[3, 1, 4])
+        .await
+        .expect("GET many failed");
+    let names: Vec<&str> = rows.iter().map(|r| r["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["Mehmet", "Ali", "Ayse"]);
+
+    // Rows can be keyed by any column, not just `id`
+    let rows = client
+        .get_many_by("students", "name", &["Ayse", "Nobody", "Zeynep"])
+        .await
+        .unwrap();
+    let ids: Vec<i64> = rows.iter().map(|r| r["id"].as_i64().unwrap()).collect();
+    assert_eq!(ids, vec![4, 2]);
+
+    // The raw IN filter is available to any client
+    let mut params = HashMap::new();
+    params.insert("name__in", "Ali,Ayse");
+    let rows = client.get("students", Some(params)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_foreign_keys_are_enforced() {
+    let mut db = EasyDB::init_in_memory("test_fk_db").expect("Failed to init DB");
+    db.create_table(
+        "students",
+        "id INTEGER PRIMARY KEY, name TEXT, school_number INTEGER UNIQUE",
+    )
+    .expect("Failed to create students table");
+    db.create_table(
+        "grades",
+        "id INTEGER PRIMARY KEY, school_number INTEGER REFERENCES students(school_number), score INTEGER",
+    )
+    .expect("Failed to create grades table");
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    client
+        .post("students", json!({"name": "Ali", "school_number": 101}))
+        .await
+        .unwrap();
+    let res = client
+        .post("grades", json!({"school_number": 101, "score": 90}))
+        .await
+        .unwrap();
+    assert_eq!(res["status"], "success");
+
+    // A grade for an unknown student is rejected
+    let res = client
+        .post("grades", json!({"school_number": 999, "score": 50}))
+        .await
+        .unwrap();
+    assert_eq!(res["error"], "foreign key constraint failed");
+
+    // Deleting a student that still has grades would orphan them
+    let res = client.delete("students", 1).await.unwrap();
+    assert_eq!(res["error"], "foreign key constraint failed");
+}
+
+#[tokio::test]
+async fn test_api_key_rejects_before_handlers() {
+    let mut db = EasyDB::init_in_memory("test_auth_db")
+        .expect("Failed to init DB")
+        .with_api_key("secret-key");
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+
+    let port = serve(db).await;
+
+    let http = reqwest::Client::new();
+    let url = format!("http://localhost:{}/logs", port);
+
+    // Without a key the write never reaches the handler
+    let res = http
+        .post(&url)
+        .json(&json!({"message": "sneaky"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    // CORS sits outside auth: preflights succeed and errors carry CORS headers
+    let res = http
+        .request(reqwest::Method::OPTIONS, &url)
+        .header("Origin", "http://example.com")
+        .header("Access-Control-Request-Method", "POST")
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let res = http
+        .get(&url)
+        .header("Origin", "http://example.com")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
+    assert!(res.headers().contains_key("access-control-allow-origin"));
+
+    // Bearer tokens are accepted as well
+    let res = http
+        .get(&url)
+        .header("Authorization", "Bearer secret-key")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+
+    let client = EasyClient::new("localhost", port).with_api_key("secret-key");
+    client
+        .post("logs", json!({"message": "allowed"}))
+        .await
+        .expect("POST failed");
+    let rows = client.get("logs", None).await.expect("GET failed");
+    let list = rows.as_array().unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0]["message"], "allowed");
+}
+
+#[tokio::test]
+async fn test_embed_children_on_single_record() {
+    let mut db = EasyDB::init_in_memory("test_embed_db").expect("Failed to init DB");
+    db.create_table(
+        "students",
+        "id INTEGER PRIMARY KEY, name TEXT, school_number INTEGER UNIQUE",
+    )
+    .expect("Failed to create students table");
+    db.create_table(
+        "grades",
+        "id INTEGER PRIMARY KEY, school_number INTEGER, lesson TEXT, score INTEGER",
+    )
+    .expect("Failed to create grades table");
+    db.add_relation("students", "grades", "school_number")
+        .expect("Failed to add relation");
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    client
+        .post("students", json!({"name": "Zeynep", "school_number": 102}))
+        .await
+        .unwrap();
+    for (lesson, score) in [("Mathematics", 100), ("Physics", 95)] {
+        client
+            .post(
+                "grades",
+                json!({"school_number": 102, "lesson": lesson, "score": score}),
+            )
+            .await
+            .unwrap();
+    }
+
+    let mut params = HashMap::new();
+    params.insert("_embed", "grades");
+    let student = client
+        .get_by_id("students", 1, Some(params))
+        .await
+        .expect("GET by id failed");
+    assert_eq!(student["name"], "Zeynep");
+    let grades = student["grades"]
+        .as_array()
+        .expect("Missing embedded grades");
+    assert_eq!(grades.len(), 2);
+    assert_eq!(grades[0]["lesson"], "Mathematics");
+
+    let missing = client.get_by_id("students", 99, None).await.unwrap();
+    assert_eq!(missing["error"], "Record not found");
+}
+
+#[tokio::test]
+async fn test_reopen_serves_replaced_file() {
+    let dir = temp_dir("reopen");
+    let (path, backup_path) = (dir.join("students.db"), dir.join("backup.db"));
+
+    let mut db = EasyDB::init_at(&path).expect("Failed to init DB");
+    db.create_table("students", "id INTEGER PRIMARY KEY, name TEXT")
+        .expect("Failed to create students table");
+
+    let opened = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = std::sync::Arc::clone(&opened);
+    db.on_connect(move |_| {
+        counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(())
+    })
+    .expect("Failed to register on_connect");
+    assert_eq!(opened.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .execute("INSERT INTO students (name) VALUES ('Old')", [])
+        .unwrap();
+
+    // Prepare a "backup" with different data and restore it over the original
+    let backup = rusqlite::Connection::open(&backup_path).unwrap();
+    backup
+        .execute_batch(
+            "CREATE TABLE students (id INTEGER PRIMARY KEY, name TEXT);
+             INSERT INTO students (name) VALUES ('New');",
+        )
+        .unwrap();
+    drop(backup);
+    std::fs::copy(&backup_path, &path).unwrap();
+
+    db.reopen().expect("Failed to reopen");
+    assert_eq!(opened.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    let rows = client.get("students", None).await.unwrap();
+    let rows = rows.as_array().unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["name"], "New");
+
+    let mut memory = EasyDB::init_in_memory("test_reopen_memory").unwrap();
+    assert!(memory.reopen().is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Student {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<i64>,
+    name: String,
+    age: i64,
+}
+
+#[tokio::test]
+async fn test_typed_client_round_trip() {
+    let port = start_test_server("test_typed_db").await;
+    let client = EasyClient::new("localhost", port);
+
+    let ali = Student {
+        id: None,
+        name: "Ali".to_string(),
+        age: 20,
+    };
+    let res = client.post("students", &ali).await.expect("POST failed");
+    assert_eq!(res["status"], "success");
+
+    let students: Vec<Student> = client.get_as("students", None).await.expect("GET failed");
+    assert_eq!(students, vec![Student { id: Some(1), ..ali }]);
+
+    let one: Student = client
+        .get_by_id_as("students", 1, None)
+        .await
+        .expect("GET by id failed");
+    assert_eq!(one.name, "Ali");
+
+    // Server errors and schema mismatches surface as errors
+    let missing = client.get_by_id_as::<Student>("students", 99, None).await;
+    assert!(missing
+        .unwrap_err()
+        .to_string()
+        .contains("Record not found"));
+    client.post("logs", json!({"message": "hi"})).await.unwrap();
+    assert!(client.get_as::<Student>("logs", None).await.is_err());
+}
+
+#[tokio::test]
+async fn test_client_timeout_and_retries() {
+    let attempts = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = std::sync::Arc::clone(&attempts);
+
+    // A server that accepts connections but never answers
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0))
+        .await
+        .unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let mut open = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            open.push(stream);
+        }
+    });
+
+    let client = EasyClient::new("127.0.0.1", port)
+        .with_timeout(Duration::from_millis(200))
+        .unwrap()
+        .with_retries(2);
+
+    let started = std::time::Instant::now();
+    assert!(client.get("students", None).await.is_err());
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+    // POST is not idempotent, so it is never retried
+    attempts.store(0, std::sync::atomic::Ordering::SeqCst);
+    assert!(client
+        .post("students", json!({"name": "Ali"}))
+        .await
+        .is_err());
+    assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_schema_includes_column_descriptions() {
+    let mut db = EasyDB::init_in_memory("test_schema_db").expect("Failed to init DB");
+    db.create_table(
+        "users",
+        "id INTEGER PRIMARY KEY, email TEXT NOT NULL, password TEXT",
+    )
+    .expect("Failed to create users table");
+    db.describe_column("users", "email", "Login address, unique per user")
+        .unwrap();
+    db.hide_column("users", "password").unwrap();
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    let schema = client.get("users/schema", None).await.unwrap();
+    assert_eq!(schema["table"], "users");
+
+    // The hidden column is not listed
+    assert_eq!(
+        schema["columns"],
+        json!([
+            {"name": "id", "type": "INTEGER", "notnull": false, "pk": true, "description": null},
+            {
+                "name": "email",
+                "type": "TEXT",
+                "notnull": true,
+                "pk": false,
+                "description": "Login address, unique per user"
+            }
+        ])
+    );
+}
+
+/// A clock that only moves when the test says so
+struct FakeClock(std::sync::Arc<std::sync::Mutex<std::time::SystemTime>>);
+
+impl Clock for FakeClock {
+    fn now(&self) -> std::time::SystemTime {
+        *self.0.lock().unwrap()
+    }
+}
+
+#[tokio::test]
+async fn test_auto_timestamps_use_injected_clock() {
+    let time = std::sync::Arc::new(std::sync::Mutex::new(
+        std::time::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123),
+    ));
+    let mut db = EasyDB::init_in_memory("test_clock_db")
+        .expect("Failed to init DB")
+        .with_clock(FakeClock(std::sync::Arc::clone(&time)));
+    db.create_table(
+        "notes",
+        "id INTEGER PRIMARY KEY, body TEXT, created_at TEXT, updated_at TEXT",
+    )
+    .expect("Failed to create notes table");
+    db.auto_timestamps("notes").unwrap();
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    client
+        .post("notes", json!({"body": "draft", "created_at": "forged"}))
+        .await
+        .unwrap();
+    let note = client.get_by_id("notes", 1, None).await.unwrap();
+    assert_eq!(note["created_at"], "2023-11-14T22:13:20.123Z");
+    assert_eq!(note["updated_at"], "2023-11-14T22:13:20.123Z");
+
+    *time.lock().unwrap() += Duration::from_secs(60);
+    client
+        .put("notes", 1, json!({"body": "final"}))
+        .await
+        .unwrap();
+    let note = client.get_by_id("notes", 1, None).await.unwrap();
+    assert_eq!(note["created_at"], "2023-11-14T22:13:20.123Z");
+    assert_eq!(note["updated_at"], "2023-11-14T22:14:20.123Z");
+}
+
+#[tokio::test]
+async fn test_clients_share_one_reqwest_client() {
+    let port = start_test_server("test_shared_client_db").await;
+
+    let http = reqwest::Client::new();
+    let writer = EasyClient::new("localhost", port).with_client(http.clone());
+    let reader = EasyClient::new("localhost", port).with_client(http);
+
+    for i in 0..5 {
+        writer
+            .post("logs", json!({"message": format!("entry {}", i)}))
+            .await
+            .unwrap();
+    }
+    let rows = reader.get("logs", None).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 5);
+}
+
+#[tokio::test]
+async fn test_health_endpoint_without_tables() {
+    let db = EasyDB::init_in_memory("test_health_db")
+        .expect("Failed to init DB")
+        .with_api_key("secret");
+
+    let port = serve(db).await;
+
+    // Probes carry no API key, so the health check is exempt from auth
+    let res = reqwest::get(format!("http://localhost:{}/_health", port))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["status"], "ok");
+
+    let res = reqwest::get(format!("http://localhost:{}/students", port))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+}
+
+#[tokio::test]
+async fn test_strict_hidden_columns_reject_filters() {
+    let mut db = EasyDB::init_in_memory("test_strict_hidden_db")
+        .expect("Failed to init DB")
+        .with_strict_hidden_columns(true);
+    db.create_table("users", "id INTEGER PRIMARY KEY, name TEXT, pin TEXT")
+        .expect("Failed to create users table");
+    db.hide_column("users", "pin").unwrap();
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    client
+        .post("users", json!({"name": "Ali", "pin": "1234"}))
+        .await
+        .unwrap();
+
+    for query in ["pin=1234", "pin__in=1234,0000", "_sort=pin"] {
+        let res = reqwest::get(format!("http://localhost:{}/users?{}", port, query))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 400, "query should be rejected: {}", query);
+    }
+
+    let res = reqwest::get(format!("http://localhost:{}/users?name=Ali", port))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+}
+
+#[tokio::test]
+async fn test_batched_writes_flush_by_size_and_on_shutdown() {
+    let dir = temp_dir("batch");
+    let path = dir.join("logs.db");
+    let mut db = EasyDB::init_at(&path).expect("Failed to init DB");
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+    // The interval is long enough that only the size limit and shutdown flush
+    db.batch_writes("logs", 25, Duration::from_secs(60))
+        .unwrap();
+
+    let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+    let server = db.bind(0).await.unwrap();
+    let port = server.local_addr().port();
+    let server = tokio::spawn(server.serve_with_shutdown(async {
+        let _ = stop_rx.await;
+    }));
+
+    let http = reqwest::Client::new();
+    for i in 0..110 {
+        let res = http
+            .post(format!("http://localhost:{}/logs", port))
+            .json(&json!({"message": format!("entry {}", i)}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 202);
+    }
+
+    // Four full batches are written without waiting for the interval
+    let client = EasyClient::new("localhost", port);
+    let mut count = 0;
+    for _ in 0..100 {
+        count = client
+            .get("logs", None)
+            .await
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .len();
+        if count == 100 {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(count, 100);
+
+    // The remaining partial batch is flushed when the server stops
+    stop_tx.send(()).unwrap();
+    tokio::time::timeout(Duration::from_secs(2), server)
+        .await
+        .expect("Server did not stop")
+        .unwrap()
+        .unwrap();
+    let stored: i64 = rusqlite::Connection::open(&path)
+        .unwrap()
+        .query_row("SELECT COUNT(*) FROM logs", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(stored, 110);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_csv_export_quotes_and_filters() {
+    let mut db = EasyDB::init_in_memory("test_csv_export_db").expect("Failed to init DB");
+    db.create_table(
+        "notes",
+        "id INTEGER PRIMARY KEY, title TEXT, body TEXT, secret TEXT",
+    )
+    .expect("Failed to create notes table");
+    db.hide_column("notes", "secret").unwrap();
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    client
+        .post(
+            "notes",
+            json!({"title": "Plain", "body": "one, two", "secret": "x"}),
+        )
+        .await
+        .unwrap();
+    client
+        .post(
+            "notes",
+            json!({"title": "Quoted", "body": "say \"hi\"\nnext line", "secret": "y"}),
+        )
+        .await
+        .unwrap();
+
+    let res = reqwest::get(format!("http://localhost:{}/notes.csv?_sort=id", port))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert!(res.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/csv"));
+    assert_eq!(
+        res.headers()["content-disposition"],
+        "attachment; filename=\"notes.csv\""
+    );
+    assert_eq!(
+        res.text().await.unwrap(),
+        "id,title,body\n1,Plain,\"one, two\"\n2,Quoted,\"say \"\"hi\"\"\nnext line\"\n"
+    );
+
+    // Same filter params as the JSON listing
+    let res = reqwest::get(format!("http://localhost:{}/notes.csv?title=Quoted", port))
+        .await
+        .unwrap();
+    let text = res.text().await.unwrap();
+    assert!(text.starts_with("id,title,body\n2,"));
+    assert!(!text.contains("Plain"));
+}
+
+#[tokio::test]
+async fn test_csv_import_reports_bad_rows() {
+    let mut db = EasyDB::init_in_memory("test_csv_import_db").expect("Failed to init DB");
+    db.create_table(
+        "students",
+        "id INTEGER PRIMARY KEY, name TEXT NOT NULL, school_number INTEGER UNIQUE",
+    )
+    .expect("Failed to create students table");
+
+    let port = serve(db).await;
+
+    let http = reqwest::Client::new();
+    let url = format!("http://localhost:{}/students/import", port);
+    let csv = "name,school_number\n\"Yılmaz, Ali\",101\nAyşe,102,extra\nMehmet,101\nZeynep,103\n";
+
+    // Strict mode rolls back everything on the first problem
+    let res = http
+        .post(format!("{}?strict=true", url))
+        .header("Content-Type", "text/csv")
+        .body(csv)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["inserted"], 0);
+
+    let client = EasyClient::new("localhost", port);
+    let rows = client.get("students", None).await.unwrap();
+    assert!(rows.as_array().unwrap().is_empty());
+
+    // Otherwise good rows are kept and bad ones reported by line
+    let res = http
+        .post(&url)
+        .header("Content-Type", "text/csv")
+        .body(csv)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["inserted"], 2);
+    let lines: Vec<i64> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["line"].as_i64().unwrap())
+        .collect();
+    assert_eq!(lines, vec![3, 4]);
+
+    let mut params = HashMap::new();
+    params.insert("_sort", "school_number");
+    let rows = client.get("students", Some(params)).await.unwrap();
+    assert_eq!(rows[0]["name"], "Yılmaz, Ali");
+    assert_eq!(rows[1]["name"], "Zeynep");
+
+    let res = http.post(&url).body(csv).send().await.unwrap();
+    assert_eq!(res.status(), 415);
+}
+
+#[tokio::test]
+async fn test_csv_null_token() {
+    let mut db = EasyDB::init_in_memory("test_csv_null_db")
+        .expect("Failed to init DB")
+        .with_csv_null("NULL");
+    db.create_table("students", "id INTEGER PRIMARY KEY, name TEXT, age INTEGER")
+        .expect("Failed to create students table");
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    client
+        .post("students", json!({"name": "Ali"}))
+        .await
+        .unwrap();
+
+    let text = reqwest::get(format!("http://localhost:{}/students.csv", port))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(text, "id,name,age\n1,Ali,NULL\n");
+
+    // Importing the export back keeps the value NULL
+    let res = reqwest::Client::new()
+        .post(format!("http://localhost:{}/students/import", port))
+        .header("Content-Type", "text/csv")
+        .body("name,age\nAyşe,NULL\n")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let rows = client.get_by_id("students", 2, None).await.unwrap();
+    assert!(rows["age"].is_null());
+}
+
+#[tokio::test]
+async fn test_ddl_endpoint_returns_create_statement() {
+    let port = start_test_server("test_ddl_db").await;
+    let client = EasyClient::new("localhost", port);
+
+    let ddl = client.get("students/ddl", None).await.unwrap();
+    assert_eq!(ddl["table"], "students");
+    let sql = ddl["sql"].as_str().expect("Missing DDL");
+    assert!(sql.starts_with("CREATE TABLE \"students\""));
+    assert!(sql.contains("id INTEGER PRIMARY KEY"));
+    assert!(sql.contains("gpa REAL"));
+
+    // Only exposed tables have the route
+    let res = reqwest::get(format!("http://localhost:{}/sqlite_master/ddl", port))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+}
+
+#[tokio::test]
+async fn test_subscribe_streams_table_changes() {
+    use futures_util::StreamExt;
+
+    let port = start_test_server("test_subscribe_db").await;
+
+    let (mut socket, _) =
+        tokio_tungstenite::connect_async(format!("ws://localhost:{}/students/subscribe", port))
+            .await
+            .expect("WebSocket handshake failed");
+
+    let client = EasyClient::new("localhost", port);
+    client
+        .post("students", json!({"name": "Ali", "age": 20}))
+        .await
+        .unwrap();
+    // Changes to other tables are not sent to this socket
+    client
+        .post("logs", json!({"message": "ignored"}))
+        .await
+        .unwrap();
+    client.put("students", 1, json!({"age": 21})).await.unwrap();
+    client.delete("students", 1).await.unwrap();
+
+    let mut events = Vec::new();
+    for _ in 0..3 {
+        let msg = tokio::time::timeout(Duration::from_secs(2), socket.next())
+            .await
+            .expect("No change event received")
+            .unwrap()
+            .unwrap();
+        let event: serde_json::Value = serde_json::from_str(msg.to_text().unwrap()).unwrap();
+        events.push(event);
+    }
+    assert_eq!(
+        events,
+        vec![
+            json!({"table": "students", "op": "insert", "id": 1}),
+            json!({"table": "students", "op": "update", "id": 1}),
+            json!({"table": "students", "op": "delete", "id": 1}),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_soft_delete_keeps_tombstoned_rows() {
+    let mut db = EasyDB::init_in_memory("test_soft_delete_db").expect("Failed to init DB");
+    db.create_table("students", "id INTEGER PRIMARY KEY, name TEXT")
+        .expect("Failed to create students table");
+    assert!(db.soft_delete("missing_table").is_err());
+    // Adds the deleted_at column, which this table lacks
+    db.soft_delete("students").unwrap();
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    for name in ["Ali", "Ayşe"] {
+        client
+            .post("students", json!({"name": name}))
+            .await
+            .unwrap();
+    }
+    let res = client.delete("students", 1).await.unwrap();
+    assert_eq!(res["status"], "success");
+
+    let rows = client.get("students", None).await.unwrap();
+    let rows = rows.as_array().unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["name"], "Ayşe");
+    let missing = client.get_by_id("students", 1, None).await.unwrap();
+    assert_eq!(missing["error"], "Record not found");
+
+    let mut params = HashMap::new();
+    params.insert("_with_deleted", "true");
+    let all = client.get("students", Some(params.clone())).await.unwrap();
+    assert_eq!(all.as_array().unwrap().len(), 2);
+    let tombstone = client.get_by_id("students", 1, Some(params)).await.unwrap();
+    assert!(tombstone["deleted_at"].is_string());
+
+    // Deleting or updating a tombstoned row again finds nothing
+    let again = client.delete("students", 1).await.unwrap();
+    assert_eq!(again["error"], "Record not found");
+    let put = client
+        .put("students", 1, json!({"name": "Veli"}))
+        .await
+        .unwrap();
+    assert_eq!(put["error"], "Record not found");
+    let patch = client
+        .patch("students", 1, json!({"name": "Veli"}))
+        .await
+        .unwrap();
+    assert_eq!(patch["error"], "Record not found");
+    let mut params = HashMap::new();
+    params.insert("_with_deleted", "true");
+    let tombstone = client.get_by_id("students", 1, Some(params)).await.unwrap();
+    assert_eq!(tombstone["name"], "Ali");
+}
+
+#[tokio::test]
+async fn test_busy_writes_are_retried() {
+    let dir = temp_dir("busy");
+    let path = dir.join("logs.db");
+    let config = DbConfig {
+        busy_timeout_ms: 0,
+        ..Default::default()
+    };
+    let open = || {
+        EasyDB::builder("logs")
+            .path(&path)
+            .config(config.clone())
+            .build()
+    };
+    let mut patient = open()
+        .expect("Failed to init DB")
+        .with_busy_retries(8, Duration::from_millis(20));
+    patient
+        .create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+    let mut impatient = open().unwrap();
+    impatient.expose_table("logs").unwrap();
+
+    let patient = serve(patient).await;
+    let impatient = serve(impatient).await;
+
+    // Another process holds the write lock for a moment
+    let hold_lock = |millis| {
+        let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+        let path = path.clone();
+        let blocker = std::thread::spawn(move || {
+            let conn = rusqlite::Connection::open(path).unwrap();
+            conn.execute_batch("BEGIN IMMEDIATE").unwrap();
+            locked_tx.send(()).unwrap();
+            std::thread::sleep(Duration::from_millis(millis));
+            conn.execute_batch("COMMIT").unwrap();
+        });
+        locked_rx.recv().unwrap();
+        blocker
+    };
+
+    let blocker = hold_lock(300);
+    let res = reqwest::Client::new()
+        .post(format!("http://localhost:{}/logs", impatient))
+        .json(&json!({"message": "gives up"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 503);
+
+    let res = reqwest::Client::new()
+        .post(format!("http://localhost:{}/logs", patient))
+        .json(&json!({"message": "waits"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    blocker.join().unwrap();
+
+    let rows = EasyClient::new("localhost", patient)
+        .get("logs", None)
+        .await
+        .unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_merge_patch_nulls_and_updates() {
+    let mut db = EasyDB::init_in_memory("test_patch_db").expect("Failed to init DB");
+    db.create_table(
+        "students",
+        "id INTEGER PRIMARY KEY, name TEXT, age INTEGER, nickname TEXT, prefs TEXT, note TEXT",
+    )
+    .expect("Failed to create students table");
+    db.column_kind("students", "prefs", ColumnKind::Json)
+        .unwrap();
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    client
+        .post(
+            "students",
+            json!({
+                "name": "Ali",
+                "age": 20,
+                "nickname": "Ace",
+                "prefs": {"theme": "dark", "lang": "tr"},
+                "note": "{\"theme\":\"dark\"}"
+            }),
+        )
+        .await
+        .unwrap();
+
+    let res = client
+        .patch(
+            "students",
+            1,
+            json!({
+                "nickname": null,
+                "age": 21,
+                "prefs": {"lang": null, "font": "mono"},
+                "note": {"font": "mono"}
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res["status"], "success");
+
+    let student = client.get_by_id("students", 1, None).await.unwrap();
+    assert_eq!(student["name"], "Ali", "absent keys stay unchanged");
+    assert_eq!(student["age"], 21);
+    assert!(student["nickname"].is_null());
+    assert_eq!(student["prefs"], json!({"theme": "dark", "font": "mono"}));
+    // Only JSON columns are merged: plain text holding JSON is replaced
+    let note: serde_json::Value = serde_json::from_str(student["note"].as_str().unwrap()).unwrap();
+    assert_eq!(note, json!({"font": "mono"}));
+
+    // Plain JSON is not a merge patch
+    let res = reqwest::Client::new()
+        .patch(format!("http://localhost:{}/students/1", port))
+        .json(&json!({"age": 30}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 415);
+    let missing = client
+        .patch("students", 99, json!({"age": 1}))
+        .await
+        .unwrap();
+    assert_eq!(missing["error"], "Record not found");
+}
+
+#[tokio::test]
+async fn test_full_text_search_ranks_and_stays_in_sync() {
+    let dir = temp_dir("fts");
+    let path = dir.join("logs.db");
+    let mut db = EasyDB::init_at(&path).expect("Failed to init DB");
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .execute(
+            "INSERT INTO logs (message) VALUES ('disk usage normal')",
+            [],
+        )
+        .unwrap();
+    db.enable_fts("logs", &["message"]).unwrap();
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    for message in ["disk full, disk error on disk 3", "user login", "cpu hot"] {
+        client
+            .post("logs", json!({"message": message}))
+            .await
+            .unwrap();
+    }
+
+    let mut params = HashMap::new();
+    params.insert("q", "disk");
+    let hits = client
+        .get("logs/search", Some(params.clone()))
+        .await
+        .unwrap();
+    let ids: Vec<i64> = hits
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["id"].as_i64().unwrap())
+        .collect();
+    // The row that existed before enable_fts is indexed too; more matches rank higher
+    assert_eq!(ids, vec![2, 1]);
+
+    client
+        .put("logs", 2, json!({"message": "resolved"}))
+        .await
+        .unwrap();
+    client.delete("logs", 1).await.unwrap();
+    let hits = client.get("logs/search", Some(params)).await.unwrap();
+    assert!(hits.as_array().unwrap().is_empty());
+
+    let res = reqwest::get(format!(
+        "http://localhost:{}/logs/search?q=%22unbalanced",
+        port
+    ))
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 400);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_full_text_search_needs_integer_primary_key() {
+    let mut db = EasyDB::init_in_memory("test_fts_key_db").expect("Failed to init DB");
+    db.create_table("notes", "note_id INTEGER PRIMARY KEY, body TEXT")
+        .unwrap();
+    db.create_table("codes", "code TEXT PRIMARY KEY, body TEXT")
+        .unwrap();
+    db.with_connection(|conn| {
+        conn.execute_batch("CREATE TABLE tags (id INTEGER PRIMARY KEY, body TEXT) WITHOUT ROWID")
+    })
+    .unwrap();
+    db.expose_table("tags").unwrap();
+
+    // Other keys give no stable rowid to index by
+    for table in ["codes", "tags"] {
+        let err = db.enable_fts(table, &["body"]).unwrap_err();
+        assert!(err.to_string().contains("INTEGER PRIMARY KEY"), "{}", err);
+    }
+    db.enable_fts("notes", &["body"]).unwrap();
+
+    let port = serve(db).await;
+    let client = EasyClient::new("localhost", port);
+    for body in ["buy milk", "call mom"] {
+        client.post("notes", json!({"body": body})).await.unwrap();
+    }
+    let mut params = HashMap::new();
+    params.insert("q", "mom");
+    let hits = client.get("notes/search", Some(params)).await.unwrap();
+    assert_eq!(hits[0]["note_id"], 2);
+    assert_eq!(hits.as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_secure_delete_pragma_is_applied() {
+    let dir = temp_dir("secure_delete");
+    let config = DbConfig {
+        secure_delete: true,
+        ..Default::default()
+    };
+    let mut db = EasyDB::builder("main")
+        .path(dir.join("main.db"))
+        .config(config)
+        .build()
+        .unwrap();
+    let archive = dir.join("archive.db");
+    db.map_table_to_db("archive", archive.to_str().unwrap())
+        .unwrap();
+
+    // Read the pragma back on every connection the database holds
+    let values = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let seen = std::sync::Arc::clone(&values);
+    db.on_connect(move |conn| {
+        let on: i64 = conn.query_row("PRAGMA secure_delete", [], |r| r.get(0))?;
+        seen.lock().unwrap().push(on);
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(*values.lock().unwrap(), vec![1, 1]);
+
+    // Off unless requested
+    let mut plain = EasyDB::init_at(dir.join("plain.db")).unwrap();
+    let off = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let seen = std::sync::Arc::clone(&off);
+    plain
+        .on_connect(move |conn| {
+            let on: i64 = conn.query_row("PRAGMA secure_delete", [], |r| r.get(0))?;
+            seen.lock().unwrap().push(on);
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(*off.lock().unwrap(), vec![0]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_insert_returns_new_id() {
+    let mut db = EasyDB::init_in_memory("test_insert_db").expect("Failed to init DB");
+    db.create_table(
+        "students",
+        "id INTEGER PRIMARY KEY, name TEXT, age INTEGER, gpa REAL",
+    )
+    .unwrap();
+
+    let first = db
+        .insert("students", &json!({"name": "Ali", "age": 20, "gpa": 3.5}))
+        .unwrap();
+    let second = db
+        .insert("students", &json!({"name": "Ayşe", "age": 21}))
+        .unwrap();
+    assert_eq!((first, second), (1, 2));
+
+    let rows = db
+        .query(
+            "SELECT name, age, typeof(age) AS age_type, gpa FROM students WHERE id = ?",
+            &[json!(second)],
+        )
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![json!({"name": "Ayşe", "age": 21, "age_type": "integer", "gpa": null})]
+    );
+
+    assert!(db.insert("students", &json!({})).is_err());
+    assert!(db.insert("students", &json!({"bad name": 1})).is_err());
+    assert!(db.insert("students; DROP", &json!({"name": "x"})).is_err());
+}
+
+#[tokio::test]
+async fn test_route_prefix() {
+    for bad in ["api", "/api/", "/api//v1", "/api/{id}"] {
+        assert!(
+            EasyDB::init_in_memory("test_bad_prefix_db")
+                .unwrap()
+                .with_prefix(bad)
+                .is_err(),
+            "prefix should be rejected: {}",
+            bad
+        );
+    }
+
+    let mut db = EasyDB::init_in_memory("test_prefix_db")
+        .expect("Failed to init DB")
+        .with_prefix("/api/v1")
+        .unwrap();
+    db.create_table("students", "id INTEGER PRIMARY KEY, name TEXT")
+        .expect("Failed to create students table");
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port).with_prefix("/api/v1");
+    client
+        .post("students", json!({"name": "Ali"}))
+        .await
+        .unwrap();
+    let student = client.get_by_id("students", 1, None).await.unwrap();
+    assert_eq!(student["name"], "Ali");
+
+    for (path, status) in [
+        ("/api/v1/students", 200),
+        ("/api/v1/_health", 200),
+        ("/students", 404),
+        ("/_health", 404),
+    ] {
+        let res = reqwest::get(format!("http://localhost:{}{}", port, path))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), status, "GET {}", path);
+    }
+}
+
+#[tokio::test]
+async fn test_null_filters() {
+    let port = start_test_server("test_null_filter_db").await;
+    let client = EasyClient::new("localhost", port);
+
+    client
+        .post("students", json!({"name": "Ali", "age": 20, "gpa": 3.1}))
+        .await
+        .unwrap();
+    client
+        .post("students", json!({"name": "Ayşe", "age": 21}))
+        .await
+        .unwrap();
+    // Explicit nulls, written by POST and by PUT, must match like missing values
+    client
+        .post("students", json!({"name": "Can", "age": 22, "gpa": null}))
+        .await
+        .unwrap();
+    let res = client
+        .post("students", json!({"name": "Deniz", "age": 23, "gpa": 2.8}))
+        .await
+        .unwrap();
+    client
+        .put(
+            "students",
+            res["id"].as_i64().unwrap(),
+            json!({"gpa": null}),
+        )
+        .await
+        .unwrap();
+
+    let names = |rows: serde_json::Value| -> Vec<String> {
+        rows.as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let mut params = HashMap::new();
+    params.insert("gpa_is_null", "true");
+    let rows = client.get("students", Some(params)).await.unwrap();
+    assert_eq!(names(rows), vec!["Ayşe", "Can", "Deniz"]);
+
+    let mut params = HashMap::new();
+    params.insert("gpa_not_null", "true");
+    let rows = client.get("students", Some(params)).await.unwrap();
+    assert_eq!(names(rows), vec!["Ali"]);
+
+    let mut params = HashMap::new();
+    params.insert("gpa_is_null", "false");
+    let rows = client.get("students", Some(params)).await.unwrap();
+    assert_eq!(names(rows), vec!["Ali"]);
+
+    let res = reqwest::get(format!(
+        "http://localhost:{}/students?gpa_is_null=yes",
+        port
+    ))
+    .await
+    .unwrap();
+    assert_eq!(res.status(), 400);
+}
+
+#[tokio::test]
+async fn test_json_null_is_stored_as_sql_null() {
+    let dir = temp_dir("null_write");
+    let path = dir.join("students.db");
+    let mut db = EasyDB::init_at(&path).expect("Failed to init DB");
+    db.create_table("students", "id INTEGER PRIMARY KEY, name TEXT, gpa REAL")
+        .expect("Failed to create students table");
+    let port = serve(db).await;
+    let client = EasyClient::new("localhost", port);
+
+    client
+        .post("students", json!({"name": "Ali", "gpa": null}))
+        .await
+        .unwrap();
+    let res = client
+        .post("students", json!({"name": "Ayşe", "gpa": 3.4}))
+        .await
+        .unwrap();
+    client
+        .put(
+            "students",
+            res["id"].as_i64().unwrap(),
+            json!({"gpa": null}),
+        )
+        .await
+        .unwrap();
+
+    let nulls: i64 = rusqlite::Connection::open(&path)
+        .unwrap()
+        .query_row("SELECT COUNT(*) FROM students WHERE gpa IS NULL", [], |r| {
+            r.get(0)
+        })
+        .unwrap();
+    assert_eq!(nulls, 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_column_mask_by_scope() {
+    let mut db = EasyDB::init_in_memory("test_mask_db")
+        .expect("Failed to init DB")
+        .with_api_key("viewer")
+        .with_scoped_api_key("admin", &["pii"]);
+    db.create_table("users", "id INTEGER PRIMARY KEY, name TEXT, email TEXT")
+        .expect("Failed to create users table");
+    db.mask_column("users", "email", "pii", |v| {
+        let email = v.as_str().unwrap_or_default();
+        let (user, domain) = email.split_once('@').unwrap_or((email, ""));
+        json!(format!("{}***@{}", &user[..1], domain))
+    })
+    .unwrap();
+
+    let port = serve(db).await;
+
+    let viewer = EasyClient::new("localhost", port).with_api_key("viewer");
+    let admin = EasyClient::new("localhost", port).with_api_key("admin");
+    viewer
+        .post("users", json!({"name": "Ali", "email": "ali@example.com"}))
+        .await
+        .unwrap();
+
+    let rows = viewer.get("users", None).await.unwrap();
+    assert_eq!(rows[0]["email"], "a***@example.com");
+    let row = viewer.get_by_id("users", 1, None).await.unwrap();
+    assert_eq!(row["email"], "a***@example.com");
+
+    let rows = admin.get("users", None).await.unwrap();
+    assert_eq!(rows[0]["email"], "ali@example.com");
+    let row = admin.get_by_id("users", 1, None).await.unwrap();
+    assert_eq!(row["email"], "ali@example.com");
+}
+
+#[tokio::test]
+async fn test_scan_guard() {
+    let mut db = EasyDB::init_in_memory("test_scan_guard_db")
+        .expect("Failed to init DB")
+        .with_scan_guard(10);
+    db.create_table("orders", "id INTEGER PRIMARY KEY, code TEXT, note TEXT")
+        .expect("Failed to create orders table");
+    db.create_index("idx_orders_code", "orders", &["code"], false)
+        .unwrap();
+    for i in 0..20 {
+        db.insert("orders", &json!({"code": format!("c{}", i), "note": "x"}))
+            .unwrap();
+    }
+
+    let port = serve(db).await;
+
+    let get = |path: &str| reqwest::get(format!("http://localhost:{}{}", port, path));
+
+    let res = get("/orders").await.unwrap();
+    assert_eq!(res.status(), 400);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("index"));
+
+    // Filtering on a column without an index still scans
+    assert_eq!(get("/orders?note=x").await.unwrap().status(), 400);
+    assert_eq!(get("/orders.csv").await.unwrap().status(), 400);
+
+    let res = get("/orders?code=c7").await.unwrap();
+    assert_eq!(res.status(), 200);
+    let rows: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_unknown_table_lists_available() {
+    let port = start_test_server("test_unknown_table_db").await;
+
+    let res = reqwest::get(format!("http://localhost:{}/studnets", port))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["error"], "unknown table");
+    assert_eq!(body["available"], json!(["students", "logs"]));
+
+    // Unknown paths below a real table are plain 404s
+    let res = reqwest::get(format!("http://localhost:{}/students/1/extra", port))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["error"], "Not found");
+}
+
+#[tokio::test]
+async fn test_unknown_column_rejected() {
+    let port = start_test_server("test_unknown_column_db").await;
+    let client = EasyClient::new("localhost", port);
+    let base = format!("http://localhost:{}", port);
+
+    let res = reqwest::get(format!("{}/students?nonexistent=5", base))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["error"], "unknown column");
+    assert_eq!(body["column"], "nonexistent");
+
+    let res = reqwest::get(format!("{}/students?_sort=height", base))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+
+    let res = client
+        .post("students", json!({"name": "Ali", "height": 180}))
+        .await
+        .unwrap();
+    assert_eq!(res["error"], "unknown column");
+    assert_eq!(res["column"], "height");
+}
+
+#[tokio::test]
+async fn test_drop_and_truncate_table() {
+    let mut db = EasyDB::init_in_memory("test_drop_db").expect("Failed to init DB");
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+    db.create_table("scratch", "id INTEGER PRIMARY KEY, note TEXT")
+        .expect("Failed to create scratch table");
+    db.enable_fts("scratch", &["note"]).unwrap();
+    db.drop_table("scratch")
+        .expect("Failed to drop scratch table");
+    assert!(db.query("SELECT * FROM scratch", &[]).is_err());
+    // The search index goes with it
+    assert!(db.query("SELECT * FROM scratch_fts", &[]).is_err());
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    let http = reqwest::Client::new();
+    let url = format!("http://localhost:{}/logs", port);
+
+    let res = reqwest::get(format!("http://localhost:{}/scratch", port))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+
+    client.post("logs", json!({"message": "a"})).await.unwrap();
+    client.post("logs", json!({"message": "b"})).await.unwrap();
+
+    let res = http.delete(&url).send().await.unwrap();
+    assert_eq!(res.status(), 400);
+    assert_eq!(
+        client
+            .get("logs", None)
+            .await
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .len(),
+        2
+    );
+
+    let res = http
+        .delete(&url)
+        .header("X-Confirm-Truncate", "true")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["deleted"], 2);
+    assert!(client
+        .get("logs", None)
+        .await
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_where_filter_groups() {
+    let port = start_test_server("test_where_db").await;
+    let client = EasyClient::new("localhost", port);
+
+    for (name, age, gpa) in [("Ali", 17, 3.0), ("Ayşe", 20, 3.95), ("Can", 22, 2.5)] {
+        client
+            .post("students", json!({"name": name, "age": age, "gpa": gpa}))
+            .await
+            .unwrap();
+    }
+    let names = |rows: serde_json::Value| -> Vec<String> {
+        rows.as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let mut params = HashMap::new();
+    params.insert("_where", r#"{"or":[{"age_lt":18},{"gpa_gt":3.9}]}"#);
+    params.insert("_sort", "age");
+    let rows = client.get("students", Some(params)).await.unwrap();
+    assert_eq!(names(rows), vec!["Ali", "Ayşe"]);
+
+    // Groups nest, and flat filters are AND-ed with the tree
+    let mut params = HashMap::new();
+    params.insert(
+        "_where",
+        r#"{"and":[{"age_gte":18},{"or":[{"name":"Can"},{"gpa__in":[3.95]}]}]}"#,
+    );
+    params.insert("name_ne", "Can");
+    let rows = client.get("students", Some(params)).await.unwrap();
+    assert_eq!(names(rows), vec!["Ayşe"]);
+
+    let mut params = HashMap::new();
+    params.insert("age_lte", "20");
+    params.insert("_sort", "age");
+    let rows = client.get("students", Some(params)).await.unwrap();
+    assert_eq!(names(rows), vec!["Ali", "Ayşe"]);
+
+    for bad in [
+        r#"{"or":[]}"#,
+        r#"{"age_lt":[1,2]}"#,
+        r#"{"age) OR 1=1 --":1}"#,
+        r#"{"or":[{"height_gt":1}]}"#,
+        "not json",
+    ] {
+        let mut params = HashMap::new();
+        params.insert("_where", bad);
+        let res = client.get("students", Some(params)).await.unwrap();
+        assert!(res["error"].is_string(), "_where={}", bad);
+    }
+}
+
+#[tokio::test]
+async fn test_max_body_size() {
+    let mut db = EasyDB::init_in_memory("test_body_limit_db")
+        .expect("Failed to init DB")
+        .with_max_body_size(1024);
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+
+    let port = serve(db).await;
+
+    let http = reqwest::Client::new();
+    let url = format!("http://localhost:{}/logs", port);
+
+    let res = http
+        .post(&url)
+        .json(&json!({"message": "x".repeat(2048)}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+
+    let res = http
+        .post(&url)
+        .json(&json!({"message": "small"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_response_compression() {
+    let mut db = EasyDB::init_in_memory("test_compression_db")
+        .expect("Failed to init DB")
+        .with_compression(true);
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+    for i in 0..50 {
+        db.insert(
+            "logs",
+            &json!({"message": format!("log line number {}", i)}),
+        )
+        .unwrap();
+    }
+
+    let port = serve(db).await;
+
+    let http = reqwest::Client::new();
+    for path in ["logs", "logs.csv"] {
+        let url = format!("http://localhost:{}/{}", port, path);
+        let res = http
+            .get(&url)
+            .header("Accept-Encoding", "gzip")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-encoding"], "gzip", "{}", path);
+
+        // Clients that don't ask for it get the plain body
+        let res = http.get(&url).send().await.unwrap();
+        assert!(!res.headers().contains_key("content-encoding"));
+    }
+}
+
+#[tokio::test]
+async fn test_streamed_listing() {
+    let mut db = EasyDB::init_in_memory("test_stream_db").expect("Failed to init DB");
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+    for i in 0..250 {
+        db.insert("logs", &json!({"message": format!("line {}", i)}))
+            .unwrap();
+    }
+
+    let port = serve(db).await;
+
+    let base = format!("http://localhost:{}/logs", port);
+    let res = reqwest::get(format!("{}?_stream=true", base))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-type"], "application/json");
+    let streamed: serde_json::Value = res.json().await.unwrap();
+    let buffered: serde_json::Value = reqwest::get(&base).await.unwrap().json().await.unwrap();
+    assert_eq!(streamed.as_array().unwrap().len(), 250);
+    assert_eq!(streamed, buffered);
+
+    // Filters apply, and bad requests are still reported with a status
+    let res = reqwest::get(format!("{}?_stream=true&id_gt=248", base))
+        .await
+        .unwrap();
+    let rows: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(
+        rows,
+        json!([{"id": 249, "message": "line 248"}, {"id": 250, "message": "line 249"}])
+    );
+    let res = reqwest::get(format!("{}?_stream=true&nope=1", base))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let res = reqwest::get(format!("{}?_stream=true&_sort=id&_limit=0", base))
+        .await
+        .unwrap();
+    let rows: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(rows, json!([]));
+}
+
+#[tokio::test]
+async fn test_row_errors_fail_the_listing() {
+    let mut db = EasyDB::init_in_memory("test_row_error_db").expect("Failed to init DB");
+    db.create_table("readings", "id INTEGER PRIMARY KEY, value INTEGER")
+        .expect("Failed to create readings table");
+    for value in [json!(1), json!(i64::MIN), json!(3)] {
+        db.insert("readings", &json!({"value": value})).unwrap();
+    }
+    // abs() of the smallest integer raises "integer overflow" on that row only
+    db.query(
+        "CREATE VIEW magnitudes AS SELECT id, abs(value) AS magnitude FROM readings",
+        &[],
+    )
+    .unwrap();
+    db.expose_table("magnitudes").unwrap();
+
+    let port = serve(db).await;
+
+    let res = reqwest::get(format!("http://localhost:{}/magnitudes", port))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 500);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("overflow"));
+
+    // Without the bad row the listing works
+    let res = reqwest::get(format!("http://localhost:{}/magnitudes?id_ne=2", port))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+}
+
+#[tokio::test]
+async fn test_panicking_request_does_not_poison_the_server() {
+    let mut db = EasyDB::init_in_memory("test_poison_db").expect("Failed to init DB");
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+    // The mask runs while the connection is locked, so a panic here poisons it
+    db.mask_column("logs", "message", "debug", |v| {
+        assert_ne!(v, "boom", "mask blew up");
+        v.clone()
+    })
+    .unwrap();
+    db.insert("logs", &json!({"message": "fine"})).unwrap();
+    db.insert("logs", &json!({"message": "boom"})).unwrap();
+
+    let port = serve(db).await;
+
+    let base = format!("http://localhost:{}", port);
+    assert!(reqwest::get(format!("{}/logs", base)).await.is_err());
+
+    let res = reqwest::get(format!("{}/logs/1", base)).await.unwrap();
+    assert_eq!(res.status(), 200);
+    let client = EasyClient::new("localhost", port);
+    let res = client
+        .post("logs", json!({"message": "after"}))
+        .await
+        .unwrap();
+    assert_eq!(res["status"], "success");
+    let res = reqwest::get(format!("{}/_health", base)).await.unwrap();
+    assert_eq!(res.status(), 200);
+}
+
+#[tokio::test]
+async fn test_distinct_fields() {
+    let port = start_test_server("test_distinct_db").await;
+    let client = EasyClient::new("localhost", port);
+
+    for (name, age) in [("Ali", 20), ("Ayşe", 21), ("Can", 20), ("Deniz", 22)] {
+        client
+            .post("students", json!({"name": name, "age": age}))
+            .await
+            .unwrap();
+    }
+
+    let mut params = HashMap::new();
+    params.insert("_fields", "age");
+    params.insert("_distinct", "true");
+    params.insert("_sort", "age");
+    let rows = client.get("students", Some(params)).await.unwrap();
+    assert_eq!(rows, json!([{"age": 20}, {"age": 21}, {"age": 22}]));
+
+    // Composes with filters
+    let mut params = HashMap::new();
+    params.insert("_fields", "age");
+    params.insert("_distinct", "true");
+    params.insert("age_lt", "22");
+    params.insert("_sort", "age");
+    let rows = client.get("students", Some(params)).await.unwrap();
+    assert_eq!(rows, json!([{"age": 20}, {"age": 21}]));
+
+    // Without _fields, whole rows are compared (and all differ here)
+    let mut params = HashMap::new();
+    params.insert("_distinct", "true");
+    let rows = client.get("students", Some(params)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 4);
+
+    let mut params = HashMap::new();
+    params.insert("_fields", "age,height");
+    let rows = client.get("students", Some(params)).await.unwrap();
+    assert_eq!(rows["error"], "unknown column");
+}
+
+#[test]
+fn test_init_at_path() {
+    let dir = std::env::temp_dir().join(format!("easy_db_init_at_{}", std::process::id()));
+    let path = dir.join("nested").join("data.db");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut db = EasyDB::init_at(&path).expect("Failed to init DB");
+    assert_eq!(db.db_name, "data");
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .unwrap();
+    db.insert("logs", &json!({"message": "saved"})).unwrap();
+    drop(db);
+    assert!(path.exists());
+
+    // Reopening the same path sees the data
+    let db = EasyDB::init_at(&path).unwrap();
+    let rows = db.query("SELECT message FROM logs", &[]).unwrap();
+    assert_eq!(rows, vec![json!({"message": "saved"})]);
+
+    drop(db);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_with_connection_escape_hatch() {
+    let mut db = EasyDB::init_in_memory("test_with_connection_db").expect("Failed to init DB");
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+
+    // A migration the generated API can't express
+    db.with_connection(|conn| {
+        conn.execute_batch(
+            "ALTER TABLE logs ADD COLUMN level TEXT DEFAULT 'info';
+             INSERT INTO logs (message, level) VALUES ('migrated', 'warn');",
+        )
+    })
+    .unwrap();
+    let count: i64 = db
+        .with_connection(|conn| conn.query_row("SELECT COUNT(*) FROM logs", [], |r| r.get(0)))
+        .unwrap();
+    assert_eq!(count, 1);
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    let rows = client.get("logs", None).await.unwrap();
+    assert_eq!(rows[0]["level"], "warn");
+}
+
+#[tokio::test]
+async fn test_builder_configures_everything() {
+    let dir = std::env::temp_dir().join(format!("easy_db_builder_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut db = EasyDB::builder("ignored")
+        .path(dir.join("app.db"))
+        .wal(true)
+        .api_key("secret")
+        .prefix("/api")
+        .max_body_size(1024)
+        .build()
+        .expect("Failed to build DB");
+    assert_eq!(db.db_name, "app");
+    let mode: String = db
+        .with_connection(|conn| conn.query_row("PRAGMA journal_mode", [], |r| r.get(0)))
+        .unwrap();
+    assert_eq!(mode, "wal");
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .unwrap();
+
+    // A bad prefix is reported by build()
+    assert!(EasyDB::builder("bad")
+        .in_memory()
+        .prefix("api/")
+        .build()
+        .is_err());
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port)
+        .with_prefix("/api")
+        .with_api_key("secret");
+    let res = client.post("logs", json!({"message": "hi"})).await.unwrap();
+    assert_eq!(res["status"], "success");
+
+    let http = reqwest::Client::new();
+    let res = http
+        .get(format!("http://localhost:{}/api/logs", port))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+    let res = http
+        .post(format!("http://localhost:{}/api/logs", port))
+        .header("X-API-Key", "secret")
+        .json(&json!({"message": "x".repeat(2048)}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 413);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_column_validators() {
+    let mut db = EasyDB::init_in_memory("test_validator_db").expect("Failed to init DB");
+    db.create_table(
+        "students",
+        "id INTEGER PRIMARY KEY, name TEXT, age INTEGER, gpa REAL",
+    )
+    .expect("Failed to create students table");
+    db.add_validator("students", "age", |v| match v.as_i64() {
+        Some(age) if (0..=150).contains(&age) => Ok(()),
+        _ => Err("out of range".to_string()),
+    })
+    .unwrap();
+    db.add_validator("students", "name", |v| match v.as_str() {
+        Some(name) if !name.trim().is_empty() => Ok(()),
+        _ => Err("must be non-empty".to_string()),
+    })
+    .unwrap();
+
+    let port = serve(db).await;
+
+    let http = reqwest::Client::new();
+    let url = format!("http://localhost:{}/students", port);
+
+    let res = http
+        .post(&url)
+        .json(&json!({"name": " ", "age": 200}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 422);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(
+        body,
+        json!({"errors": {"age": "out of range", "name": "must be non-empty"}})
+    );
+
+    // A missing column is checked too on create
+    let res = http
+        .post(&url)
+        .json(&json!({"age": 20}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 422);
+
+    let res = http
+        .post(&url)
+        .json(&json!({"name": "Ali", "age": 20}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+
+    // Updates only check the columns they change
+    let res = http
+        .put(format!("{}/1", url))
+        .json(&json!({"gpa": 3.5}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let res = http
+        .put(format!("{}/1", url))
+        .json(&json!({"age": -1}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 422);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["errors"]["age"], "out of range");
+}
+
+#[tokio::test]
+async fn test_pagination_headers() {
+    let mut db = EasyDB::init_in_memory("test_page_headers_db").expect("Failed to init DB");
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+    for i in 0..25 {
+        let level = if i % 5 == 0 { "warn" } else { "info" };
+        db.insert("logs", &json!({"message": level})).unwrap();
+    }
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    let mut params = HashMap::new();
+    params.insert("_page", "2");
+    params.insert("_per_page", "10");
+    let page = client.get_page("logs", Some(params)).await.unwrap();
+    assert_eq!(page.rows.len(), 10);
+    assert_eq!(page.rows[0]["id"], 11);
+    assert_eq!(page.total, Some(25));
+    assert_eq!(page.next.as_deref(), Some("/logs?_page=3&_per_page=10"));
+    assert_eq!(page.prev.as_deref(), Some("/logs?_page=1&_per_page=10"));
+
+    // The count follows the filters; the last page has no next link
+    let mut params = HashMap::new();
+    params.insert("message", "info");
+    params.insert("_limit", "10");
+    params.insert("_offset", "10");
+    let page = client.get_page("logs", Some(params)).await.unwrap();
+    assert_eq!(page.total, Some(20));
+    assert_eq!(page.rows.len(), 10);
+    assert!(page.next.is_none());
+    assert_eq!(
+        page.prev.as_deref(),
+        Some("/logs?_limit=10&_offset=0&message=info")
+    );
+
+    let res = reqwest::get(format!("http://localhost:{}/logs", port))
+        .await
+        .unwrap();
+    assert!(!res.headers().contains_key("x-total-count"));
+    let res = reqwest::get(format!("http://localhost:{}/logs?_page=3", port))
+        .await
+        .unwrap();
+    assert_eq!(res.headers()["x-total-count"], "25");
+    let link = res.headers()["link"].to_str().unwrap();
+    assert!(link.contains(r#"</logs?_page=1>; rel="first""#));
+    assert!(link.contains(r#"</logs?_page=3>; rel="last""#));
+}
+
+#[tokio::test]
+async fn test_large_ids() {
+    let mut db = EasyDB::init_in_memory("test_large_id_db").expect("Failed to init DB");
+    db.create_table("events", "id INTEGER PRIMARY KEY, name TEXT")
+        .expect("Failed to create events table");
+    let id = 5_000_000_000_i64;
+    assert_eq!(
+        db.insert("events", &json!({"id": id, "name": "big"}))
+            .unwrap(),
+        id
+    );
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    let res = client
+        .put("events", id, json!({"name": "bigger"}))
+        .await
+        .unwrap();
+    assert_eq!(res["status"], "success");
+    let res = client
+        .patch("events", id, json!({"name": "biggest"}))
+        .await
+        .unwrap();
+    assert_eq!(res["status"], "success");
+    let row = client.get_by_id("events", id, None).await.unwrap();
+    assert_eq!(row["name"], "biggest");
+    let res = client.delete("events", id).await.unwrap();
+    assert_eq!(res["status"], "success");
+    assert_eq!(res["id"], id);
+}
+
+#[tokio::test]
+async fn test_text_primary_keys() {
+    let mut db = EasyDB::init_in_memory("test_text_pk_db").expect("Failed to init DB");
+    db.create_table("sessions", "id TEXT PRIMARY KEY, user TEXT")
+        .expect("Failed to create sessions table");
+    db.create_table("countries", "code TEXT PRIMARY KEY, name TEXT")
+        .expect("Failed to create countries table");
+    db.primary_key("countries", "code").unwrap();
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    let uuid = "3f2b8c1e-9a4d-4e6f-b1c2-7d8e9f0a1b2c";
+    client
+        .post("sessions", json!({"id": uuid, "user": "ali"}))
+        .await
+        .unwrap();
+    let row = client.get_by_id("sessions", uuid, None).await.unwrap();
+    assert_eq!(row["user"], "ali");
+    let res = client
+        .put("sessions", uuid, json!({"user": "ayse"}))
+        .await
+        .unwrap();
+    assert_eq!(res["status"], "success");
+    let res = client
+        .patch("sessions", uuid, json!({"user": "can"}))
+        .await
+        .unwrap();
+    assert_eq!(res["status"], "success");
+    assert_eq!(
+        client.get_by_id("sessions", uuid, None).await.unwrap()["user"],
+        "can"
+    );
+    let res = client.delete("sessions", uuid).await.unwrap();
+    assert_eq!(res["status"], "success");
+    assert_eq!(res["id"], uuid);
+
+    // A configured key column
+    client
+        .post("countries", json!({"code": "TR", "name": "Türkiye"}))
+        .await
+        .unwrap();
+    let row = client.get_by_id("countries", "TR", None).await.unwrap();
+    assert_eq!(row["name"], "Türkiye");
+
+    // Integer keys still reject non-numeric ids
+    let res = reqwest::get(format!("http://localhost:{}/logs/abc", port))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+}
+
+#[tokio::test]
+async fn test_options_reports_allowed_methods() {
+    let port = start_test_server("test_options_db").await;
+
+    let http = reqwest::Client::new();
+    let base = format!("http://localhost:{}", port);
+
+    let res = http
+        .request(reqwest::Method::OPTIONS, format!("{}/students", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::NO_CONTENT);
+    assert_eq!(
+        res.headers()["allow"],
+        "GET, HEAD, POST, PUT, DELETE, OPTIONS"
+    );
+
+    let res = http
+        .request(reqwest::Method::OPTIONS, format!("{}/students/1", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::NO_CONTENT);
+    assert_eq!(
+        res.headers()["allow"],
+        "GET, HEAD, PUT, PATCH, DELETE, OPTIONS"
+    );
+
+    // Preflights are still answered by the CORS layer
+    let res = http
+        .request(reqwest::Method::OPTIONS, format!("{}/students", base))
+        .header("Origin", "http://example.com")
+        .header("Access-Control-Request-Method", "POST")
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert!(res.headers().contains_key("access-control-allow-origin"));
+}
+
+#[cfg(feature = "blocking")]
+#[tokio::test]
+async fn test_blocking_client() {
+    let port = start_test_server("test_blocking_db").await;
+
+    // The blocking client runs its own runtime, so keep it off the test's one
+    tokio::task::spawn_blocking(move || {
+        let client = easy_db::BlockingClient::new("localhost", port);
+        client.wait_until_ready(Duration::from_secs(5)).unwrap();
+        assert!(client.ping().unwrap());
+        client
+            .post("students", json!({"name": "Ali", "age": 20, "gpa": 3.1}))
+            .unwrap();
+        client
+            .post("students", json!({"name": "Veli", "age": 22, "gpa": 2.8}))
+            .unwrap();
+
+        let rows = client.get("students", None).unwrap();
+        assert_eq!(rows.as_array().unwrap().len(), 2);
+        let res = client.put("students", 1, json!({"age": 21})).unwrap();
+        assert_eq!(res["status"], "success");
+        assert_eq!(client.get_by_id("students", 1, None).unwrap()["age"], 21);
+        let students: Vec<Student> = client
+            .query_as("students", &Query::new().eq("name", "Veli"))
+            .unwrap();
+        assert_eq!(students.len(), 1);
+        assert_eq!(students[0].age, 22);
+
+        let mut params = HashMap::new();
+        params.insert("_page", "1");
+        params.insert("_per_page", "1");
+        let page = client.get_page("students", Some(params)).unwrap();
+        assert_eq!(page.rows.len(), 1);
+        assert_eq!(page.total, Some(2));
+        assert!(page.next.is_some());
+
+        let res = client.delete("students", 2).unwrap();
+        assert_eq!(res["status"], "success");
+        assert_eq!(client.get_many("students", &[2, 1]).unwrap().len(), 1);
+    })
+    .await
+    .unwrap();
+}
+
+#[cfg(feature = "tls")]
+#[tokio::test]
+async fn test_tls_rejects_missing_or_invalid_certificates() {
+    let mut db = EasyDB::init_in_memory("test_tls_db").expect("Failed to init DB");
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+    let err = db
+        .run_server_tls(9658, "missing_cert.pem", "missing_key.pem")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("certificate not found"));
+
+    let dir = temp_dir("tls_garbage");
+    let pem = dir.join("garbage.pem");
+    std::fs::write(&pem, "not a certificate").unwrap();
+    let mut db = EasyDB::init_in_memory("test_tls_db").expect("Failed to init DB");
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+    let err = db.run_server_tls(9658, &pem, &pem).await.unwrap_err();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(err.to_string().contains("Invalid TLS certificate"));
+}
+
+#[tokio::test]
+async fn test_rate_limit_per_client() {
+    let mut db = EasyDB::init_in_memory("test_rate_limit_db")
+        .expect("Failed to init DB")
+        .with_api_key("secret-key")
+        .with_rate_limit(3, Duration::from_secs(60));
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+    let port = serve(db).await;
+
+    let http = reqwest::Client::new();
+    let url = format!("http://localhost:{}/logs", port);
+
+    // Rejected by auth before any budget is spent
+    for _ in 0..5 {
+        let res = http.get(&url).send().await.unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
+    }
+
+    for _ in 0..3 {
+        let res = http
+            .get(&url)
+            .header("X-API-Key", "secret-key")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::OK);
+    }
+    let res = http
+        .get(&url)
+        .header("X-API-Key", "secret-key")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = res.headers()["retry-after"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=20).contains(&retry_after));
+
+    // Health checks are never throttled
+    let res = http
+        .get(format!("http://localhost:{}/_health", port))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_single_returns_the_object() {
+    let port = start_test_server("test_single_db").await;
+
+    let client = EasyClient::new("localhost", port);
+    for (name, age) in [("Ali", 20), ("Veli", 22), ("Ayse", 22)] {
+        client
+            .post("students", json!({"name": name, "age": age, "gpa": 3.0}))
+            .await
+            .unwrap();
+    }
+
+    let mut params = HashMap::new();
+    params.insert("name", "Veli");
+    params.insert("_single", "true");
+    let row = client.get("students", Some(params)).await.unwrap();
+    assert_eq!(row["name"], "Veli");
+    assert_eq!(row["age"], 22);
+
+    let base = format!("http://localhost:{}/students", port);
+    let res = reqwest::get(format!("{}?name=Nobody&_single=true", base))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::NOT_FOUND);
+
+    let res = reqwest::get(format!("{}?age=22&_single=true", base))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::CONFLICT);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["count"], 2);
+}
+
+#[tokio::test]
+async fn test_filtered_bulk_delete_and_update() {
+    let mut db = EasyDB::init_in_memory("test_bulk_db").expect("Failed to init DB");
+    db.create_table(
+        "logs",
+        "id INTEGER PRIMARY KEY, message TEXT, level TEXT, archived INTEGER",
+    )
+    .expect("Failed to create logs table");
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    for (message, level) in [("a", "debug"), ("b", "debug"), ("c", "info"), ("d", "warn")] {
+        client
+            .post("logs", json!({"message": message, "level": level}))
+            .await
+            .unwrap();
+    }
+
+    let http = reqwest::Client::new();
+    let url = format!("http://localhost:{}/logs", port);
+
+    // Without the confirmation header nothing happens
+    let res = http
+        .delete(format!("{}?level=debug", url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let res = http
+        .delete(format!("{}?level=debug", url))
+        .header("X-Confirm-Bulk", "true")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["deleted"], 2);
+
+    // The bulk header alone never truncates
+    let res = http
+        .delete(&url)
+        .header("X-Confirm-Bulk", "true")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let res = http
+        .put(format!("{}?level__in=info,warn", url))
+        .header("X-Confirm-Bulk", "true")
+        .json(&json!({"archived": 1}))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["updated"], 2);
+
+    // A bulk update must be filtered
+    let res = http
+        .put(&url)
+        .header("X-Confirm-Bulk", "true")
+        .json(&json!({"archived": 0}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+
+    let rows = client.get("logs", None).await.unwrap();
+    let rows = rows.as_array().unwrap();
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().all(|r| r["archived"] == 1));
+}
+
+#[tokio::test]
+async fn test_create_unique_index() {
+    let mut db = EasyDB::init_in_memory("test_index_db").expect("Failed to init DB");
+    db.create_table(
+        "grades",
+        "id INTEGER PRIMARY KEY, school_number INTEGER, term TEXT",
+    )
+    .expect("Failed to create grades table");
+    db.create_index(
+        "idx_grades_term",
+        "grades",
+        &["school_number", "term"],
+        true,
+    )
+    .unwrap();
+    // Running it again is a no-op
+    db.create_index(
+        "idx_grades_term",
+        "grades",
+        &["school_number", "term"],
+        true,
+    )
+    .unwrap();
+    assert!(db
+        .create_index("idx_bad", "grades", &["term; DROP TABLE grades"], false)
+        .is_err());
+    assert!(db.create_index("idx_empty", "grades", &[], false).is_err());
+
+    db.insert("grades", &json!({"school_number": 102, "term": "fall"}))
+        .unwrap();
+    db.insert("grades", &json!({"school_number": 102, "term": "spring"}))
+        .unwrap();
+    assert!(db
+        .insert("grades", &json!({"school_number": 102, "term": "fall"}))
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_sort_nulls_first_or_last() {
+    let port = start_test_server("test_nulls_db").await;
+
+    let client = EasyClient::new("localhost", port);
+    for (name, gpa) in [
+        ("Ali", json!(3.2)),
+        ("Veli", json!(null)),
+        ("Ayse", json!(3.8)),
+    ] {
+        client
+            .post("students", json!({"name": name, "age": 20, "gpa": gpa}))
+            .await
+            .unwrap();
+    }
+
+    let names = |rows: serde_json::Value| -> Vec<String> {
+        rows.as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+    let mut params = HashMap::new();
+    params.insert("_sort", "gpa");
+    params.insert("_order", "desc");
+    params.insert("_nulls", "first");
+    let rows = client.get("students", Some(params.clone())).await.unwrap();
+    assert_eq!(names(rows), ["Veli", "Ayse", "Ali"]);
+
+    params.insert("_order", "asc");
+    params.insert("_nulls", "last");
+    let rows = client.get("students", Some(params.clone())).await.unwrap();
+    assert_eq!(names(rows), ["Ali", "Ayse", "Veli"]);
+
+    params.insert("_nulls", "sideways");
+    let res = client.get("students", Some(params)).await.unwrap();
+    assert!(res["error"].is_string());
+}
+
+#[tokio::test]
+async fn test_bind_to_an_ephemeral_port() {
+    let mut db = EasyDB::init_in_memory("test_bind_db").expect("Failed to init DB");
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .expect("Failed to create logs table");
+
+    let server = db.bind(0).await.expect("Failed to bind");
+    let port = server.local_addr().port();
+    assert_ne!(port, 0);
+    tokio::spawn(server.serve());
+
+    // The listener is already bound, so no wait is needed
+    let client = EasyClient::new("localhost", port);
+    client.post("logs", json!({"message": "hi"})).await.unwrap();
+    let rows = client.get("logs", None).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_json_columns_round_trip() {
+    let mut db = EasyDB::init_in_memory("test_json_column_db").expect("Failed to init DB");
+    db.create_table(
+        "students",
+        "id INTEGER PRIMARY KEY, name TEXT, metadata TEXT",
+    )
+    .expect("Failed to create students table");
+    db.column_kind("students", "metadata", ColumnKind::Json)
+        .unwrap();
+    db.insert(
+        "students",
+        &json!({"name": "Ali", "metadata": {"tags": ["honors"], "year": 2}}),
+    )
+    .unwrap();
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    let row = client.get_by_id("students", 1, None).await.unwrap();
+    assert_eq!(row["metadata"], json!({"tags": ["honors"], "year": 2}));
+
+    client
+        .post("students", json!({"name": "Veli", "metadata": ["a", "b"]}))
+        .await
+        .unwrap();
+    let row = client.get_by_id("students", 2, None).await.unwrap();
+    assert_eq!(row["metadata"], json!(["a", "b"]));
+
+    // Strings are stored as JSON too, so they come back unchanged
+    client
+        .put("students", 2, json!({"metadata": "plain"}))
+        .await
+        .unwrap();
+    let row = client.get_by_id("students", 2, None).await.unwrap();
+    assert_eq!(row["metadata"], "plain");
+
+    client
+        .patch("students", 1, json!({"metadata": {"year": 3}}))
+        .await
+        .unwrap();
+    let row = client.get_by_id("students", 1, None).await.unwrap();
+    assert_eq!(row["metadata"], json!({"tags": ["honors"], "year": 3}));
+}
+
+#[tokio::test]
+async fn test_bool_columns() {
+    let mut db = EasyDB::init_in_memory("test_bool_column_db").expect("Failed to init DB");
+    db.create_table(
+        "users",
+        "id INTEGER PRIMARY KEY, name TEXT, is_active INTEGER",
+    )
+    .expect("Failed to create users table");
+    db.column_kind("users", "is_active", ColumnKind::Bool)
+        .unwrap();
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    client
+        .post("users", json!({"name": "Ali", "is_active": true}))
+        .await
+        .unwrap();
+    client
+        .post("users", json!({"name": "Veli", "is_active": false}))
+        .await
+        .unwrap();
+
+    let row = client.get_by_id("users", 1, None).await.unwrap();
+    assert_eq!(row["is_active"], true);
+
+    let mut params = HashMap::new();
+    params.insert("is_active", "false");
+    let rows = client.get("users", Some(params)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+    assert_eq!(rows[0]["name"], "Veli");
+    assert_eq!(rows[0]["is_active"], false);
+
+    client
+        .put("users", 2, json!({"is_active": true}))
+        .await
+        .unwrap();
+    let mut params = HashMap::new();
+    params.insert("is_active", "true");
+    let rows = client.get("users", Some(params)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_datetime_columns() {
+    let mut db = EasyDB::init_in_memory("test_datetime_column_db").expect("Failed to init DB");
+    db.create_table(
+        "events",
+        "id INTEGER PRIMARY KEY, name TEXT, starts_at TEXT",
+    )
+    .expect("Failed to create events table");
+    db.column_kind("events", "starts_at", ColumnKind::DateTime)
+        .unwrap();
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    // Offsets are normalized to UTC, so the second event sorts before the first
+    client
+        .post(
+            "events",
+            json!({"name": "late", "starts_at": "2024-01-01T10:00:00+03:00"}),
+        )
+        .await
+        .unwrap();
+    client
+        .post(
+            "events",
+            json!({"name": "early", "starts_at": "2023-12-31T09:00:00Z"}),
+        )
+        .await
+        .unwrap();
+    let row = client.get_by_id("events", 1, None).await.unwrap();
+    assert_eq!(row["starts_at"], "2024-01-01T07:00:00.000Z");
+
+    let res = client
+        .post(
+            "events",
+            json!({"name": "bad", "starts_at": "next tuesday"}),
+        )
+        .await
+        .unwrap();
+    assert!(res["errors"]["starts_at"].is_string());
+
+    let mut params = HashMap::new();
+    params.insert("starts_at_gte", "2024-01-01");
+    let rows = client.get("events", Some(params)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+    assert_eq!(rows[0]["name"], "late");
+
+    let mut params = HashMap::new();
+    params.insert("starts_at_lt", "2024-01-01T08:00:00%2B02:00");
+    let rows = client.get("events", Some(params)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+    assert_eq!(rows[0]["name"], "early");
+
+    let mut params = HashMap::new();
+    params.insert("starts_at_gte", "soon");
+    let res = client.get("events", Some(params)).await.unwrap();
+    assert!(res["error"].is_string());
+}
+
+#[tokio::test]
+async fn test_introspection_can_be_disabled() {
+    let mut db = EasyDB::init_in_memory("test_introspection_db")
+        .expect("Failed to init DB")
+        .with_introspection(false);
+    db.create_table("students", "id INTEGER PRIMARY KEY, name TEXT")
+        .expect("Failed to create students table");
+    let port = serve(db).await;
+
+    let get = |path: &str| reqwest::get(format!("http://localhost:{}{}", port, path));
+    for path in ["/students/schema", "/students/ddl", "/studnets"] {
+        let res = get(path).await.unwrap();
+        assert_eq!(res.status(), 404, "{}", path);
+        let body: serde_json::Value = res.json().await.unwrap();
+        assert_eq!(body, json!({"error": "Not found"}));
+    }
+    assert_eq!(get("/students").await.unwrap().status(), 200);
+}
+
+#[tokio::test]
+async fn test_cascading_relation_deletes_children() {
+    let mut db = EasyDB::init_in_memory("test_cascade_db").expect("Failed to init DB");
+    db.create_table(
+        "students",
+        "id INTEGER PRIMARY KEY, school_number INTEGER UNIQUE, name TEXT",
+    )
+    .unwrap();
+    db.create_table(
+        "grades",
+        "id INTEGER PRIMARY KEY, school_number INTEGER, score INTEGER",
+    )
+    .unwrap();
+    db.create_table(
+        "appeals",
+        "id INTEGER PRIMARY KEY, grade_id INTEGER REFERENCES grades(id)",
+    )
+    .unwrap();
+    db.add_cascading_relation("students", "grades", "school_number")
+        .unwrap();
+    db.insert("students", &json!({"school_number": 101, "name": "Ali"}))
+        .unwrap();
+    db.insert("students", &json!({"school_number": 102, "name": "Veli"}))
+        .unwrap();
+    for (number, score) in [(101, 80), (101, 90), (102, 70)] {
+        db.insert("grades", &json!({"school_number": number, "score": score}))
+            .unwrap();
+    }
+    // Veli's grade is referenced, so deleting it fails
+    db.insert("appeals", &json!({"grade_id": 3})).unwrap();
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    let res = client.delete("students", 1).await.unwrap();
+    assert_eq!(res["status"], "success");
+    let grades = client.get("grades", None).await.unwrap();
+    assert_eq!(grades.as_array().unwrap().len(), 1);
+
+    // The failed child delete rolls the parent delete back
+    let res = client.delete("students", 2).await.unwrap();
+    assert!(res["error"].is_string());
+    let row = client.get_by_id("students", 2, None).await.unwrap();
+    assert_eq!(row["name"], "Veli");
+    let grades = client.get("grades", None).await.unwrap();
+    assert_eq!(grades.as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_client_query_builder() {
+    let port = start_test_server("test_query_builder_db").await;
+
+    let client = EasyClient::new("localhost", port);
+    for (name, age, gpa) in [
+        ("Bob", 19, 3.1),
+        ("Bob", 17, 3.9),
+        ("Ali & Veli", 20, 2.5),
+        ("Ayse", 22, 3.6),
+    ] {
+        client
+            .post("students", json!({"name": name, "age": age, "gpa": gpa}))
+            .await
+            .unwrap();
+    }
+
+    let rows = client
+        .query("students", &Query::new().eq("name", "Bob").gt("age", 18))
+        .await
+        .unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+    assert_eq!(rows[0]["age"], 19);
+
+    // Values are URL-encoded
+    let rows = client
+        .query("students", &Query::new().eq("name", "Ali & Veli"))
+        .await
+        .unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 1);
+
+    let rows = client
+        .query(
+            "students",
+            &Query::new()
+                .gte("gpa", 3.0)
+                .sort("gpa", Order::Desc)
+                .limit(2),
+        )
+        .await
+        .unwrap();
+    let names: Vec<&str> = rows
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["Bob", "Ayse"]);
+    assert_eq!(rows[0]["age"], 17);
+
+    let query = Query::new().any_of("id", &[1, 3]).fields(&["id", "name"]);
+    assert_eq!(query.to_query_string(), "id__in=1%2C3&_fields=id%2Cname");
+    let rows = client.query("students", &query).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 2);
+    assert!(rows[0].get("age").is_none());
+}
+
+#[tokio::test]
+async fn test_keyword_identifiers_are_quoted() {
+    let mut db = EasyDB::init_in_memory("test_keyword_db").unwrap();
+    db.create_table(
+        "order",
+        "id INTEGER PRIMARY KEY, \"group\" TEXT, \"select\" INTEGER",
+    )
+    .unwrap();
+    db.create_index("order_group", "order", &["group"], false)
+        .unwrap();
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    for (group, select) in [("a", 2), ("b", 1), ("a", 3)] {
+        client
+            .post("order", json!({"group": group, "select": select}))
+            .await
+            .unwrap();
+    }
+
+    let rows = client
+        .query(
+            "order",
+            &Query::new().eq("group", "a").sort("select", Order::Desc),
+        )
+        .await
+        .unwrap();
+    let selects: Vec<i64> = rows
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["select"].as_i64().unwrap())
+        .collect();
+    assert_eq!(selects, vec![3, 2]);
+
+    client
+        .patch("order", 2, json!({"select": 5}))
+        .await
+        .unwrap();
+    let row = client.get_by_id("order", 2, None).await.unwrap();
+    assert_eq!(row["select"], 5);
+
+    client.delete("order", 1).await.unwrap();
+    let rows = client.get("order", None).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_post_reports_missing_required_columns() {
+    let mut db = EasyDB::init_in_memory("test_required_db").unwrap();
+    db.create_table(
+        "people",
+        "id INTEGER PRIMARY KEY, name TEXT NOT NULL, age INTEGER NOT NULL, \
+         note TEXT, score INTEGER NOT NULL DEFAULT 0",
+    )
+    .unwrap();
+    let port = serve(db).await;
+
+    let http = reqwest::Client::new();
+    let url = format!("http://localhost:{}/people", port);
+
+    let res = http
+        .post(&url)
+        .json(&json!({"name": "X"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 422);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body, json!({"errors": {"age": "required"}}));
+
+    // An explicit null doesn't satisfy the column either
+    let res = http
+        .post(&url)
+        .json(&json!({"name": null, "age": 30}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 422);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body, json!({"errors": {"name": "required"}}));
+
+    // Nullable and defaulted columns may be left out
+    let res = http
+        .post(&url)
+        .json(&json!({"name": "X", "age": 30}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+}
+
+#[cfg(feature = "metrics")]
+#[tokio::test]
+async fn test_metrics_endpoint() {
+    let mut db = EasyDB::init_in_memory("test_metrics_db")
+        .unwrap()
+        .with_metrics();
+    db.create_table("students", "id INTEGER PRIMARY KEY, name TEXT")
+        .unwrap();
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    client.post("students", json!({"name": "A"})).await.unwrap();
+    client.get("students", None).await.unwrap();
+    let _ = reqwest::get(format!("http://localhost:{}/nowhere", port)).await;
+
+    let res = reqwest::get(format!("http://localhost:{}/metrics", port))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert!(res.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain"));
+    let body = res.text().await.unwrap();
+    assert!(
+        body.contains("easydb_requests_total{table=\"students\",method=\"POST\",status=\"201\"} 1")
+    );
+    assert!(
+        body.contains("easydb_requests_total{table=\"students\",method=\"GET\",status=\"200\"} 1")
+    );
+    assert!(body.contains("easydb_requests_total{table=\"none\",method=\"GET\",status=\"404\"} 1"));
+    assert!(
+        body.contains("easydb_request_duration_seconds_count{table=\"students\",method=\"GET\"} 1")
+    );
+    assert!(body.contains("# TYPE easydb_request_duration_seconds histogram"));
+}
+
+#[tokio::test]
+async fn test_mounted_databases_share_one_server() {
+    let mut library = EasyDB::init_in_memory("test_mount_library_db").unwrap();
+    library
+        .create_table("books", "id INTEGER PRIMARY KEY, title TEXT")
+        .unwrap();
+    let mut school = EasyDB::init_in_memory("test_mount_school_db")
+        .unwrap()
+        .mount("/library", library)
+        .unwrap();
+    school
+        .create_table("students", "id INTEGER PRIMARY KEY, name TEXT")
+        .unwrap();
+    let port = serve(school).await;
+
+    let client = EasyClient::new("localhost", port);
+    client.post("students", json!({"name": "A"})).await.unwrap();
+    client
+        .post("library/books", json!({"title": "Dune"}))
+        .await
+        .unwrap();
+    let books = client.get("library/books", None).await.unwrap();
+    assert_eq!(books[0]["title"], "Dune");
+    assert_eq!(client.get("students", None).await.unwrap()[0]["name"], "A");
+
+    // Each database keeps to its own tables
+    let res = reqwest::get(format!("http://localhost:{}/library/students", port))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+    let res = reqwest::get(format!("http://localhost:{}/library/_health", port))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    // A mount can't shadow a table
+    let mut clash = EasyDB::init_in_memory("test_mount_clash_db")
+        .unwrap()
+        .mount(
+            "/students",
+            EasyDB::init_in_memory("test_mount_inner_db").unwrap(),
+        )
+        .unwrap();
+    clash
+        .create_table("students", "id INTEGER PRIMARY KEY")
+        .unwrap();
+    assert!(clash.bind(0).await.is_err());
+    assert!(EasyDB::init_in_memory("test_mount_bad_db")
+        .unwrap()
+        .mount(
+            "library",
+            EasyDB::init_in_memory("test_mount_bad_inner_db").unwrap()
+        )
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_batch_runs_in_one_transaction() {
+    let port = start_test_server("test_batch_db").await;
+
+    let http = reqwest::Client::new();
+    let url = format!("http://localhost:{}/_batch", port);
+    let client = EasyClient::new("localhost", port);
+
+    let res = http
+        .post(&url)
+        .json(&json!([
+            {"op": "post", "table": "students", "data": {"name": "A", "age": 20}},
+            {"op": "post", "table": "logs", "data": {"message": "added A"}},
+            {"op": "put", "table": "students", "id": 1, "data": {"age": 21}},
+            {"op": "delete", "table": "logs", "id": 99, "continue_on_error": true},
+        ]))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results[0]["status"], 201);
+    assert_eq!(results[0]["body"]["id"], 1);
+    assert_eq!(results[2]["status"], 200);
+    assert_eq!(results[3]["status"], 404);
+    let student = client.get_by_id("students", 1, None).await.unwrap();
+    assert_eq!(student["age"], 21);
+
+    // A failed operation rolls back the ones before it
+    let res = http
+        .post(&url)
+        .json(&json!([
+            {"op": "post", "table": "students", "data": {"name": "B"}},
+            {"op": "delete", "table": "students", "id": 42},
+        ]))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["error"], "Batch rolled back");
+    assert_eq!(body["index"], 1);
+    let students = client.get("students", None).await.unwrap();
+    assert_eq!(students.as_array().unwrap().len(), 1);
+
+    // Operations are checked before anything runs
+    let res = http
+        .post(&url)
+        .json(&json!([
+            {"op": "post", "table": "students", "data": {"name": "C"}},
+            {"op": "post", "table": "nowhere", "data": {}},
+        ]))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["index"], 1);
+    let students = client.get("students", None).await.unwrap();
+    assert_eq!(students.as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_post_sets_location_header() {
+    let mut db = EasyDB::init_in_memory("test_location_db")
+        .unwrap()
+        .with_prefix("/api")
+        .unwrap();
+    db.create_table("students", "id INTEGER PRIMARY KEY, name TEXT")
+        .unwrap();
+    db.create_table("countries", "code TEXT PRIMARY KEY, name TEXT")
+        .unwrap();
+    db.primary_key("countries", "code").unwrap();
+    let port = serve(db).await;
+
+    let http = reqwest::Client::new();
+    let base = format!("http://localhost:{}", port);
+
+    let res = http
+        .post(format!("{}/api/students", base))
+        .json(&json!({"name": "A"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 201);
+    assert_eq!(res.headers()["location"], "/api/students/1");
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["id"], 1);
+
+    // The header can be followed as is
+    let res = http
+        .get(format!("{}/api/students/1", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+
+    // Text keys are escaped
+    let res = http
+        .post(format!("{}/api/countries", base))
+        .json(&json!({"code": "T R", "name": "Türkiye"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.headers()["location"], "/api/countries/T%20R");
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["id"], "T R");
+}
+
+#[tokio::test]
+async fn test_case_insensitive_filter() {
+    let port = start_test_server("test_ci_filter_db").await;
+
+    let client = EasyClient::new("localhost", port);
+    for name in ["John", "JOHN", "Johnny"] {
+        client
+            .post("students", json!({"name": name}))
+            .await
+            .unwrap();
+    }
+
+    let mut params = HashMap::new();
+    params.insert("name", "john");
+    let rows = client.get("students", Some(params)).await.unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 0);
+
+    let rows = client
+        .query("students", &Query::new().eq_ci("name", "john"))
+        .await
+        .unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 2);
+
+    // `_where` accepts the suffix too
+    let rows = client
+        .query(
+            "students",
+            &Query::new().param("_where", json!({"or": [{"name_ci": "johnny"}, {"id": 1}]})),
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_client_request_returns_status() {
+    let port = start_test_server("test_client_status_db").await;
+    let client = EasyClient::new("localhost", port);
+
+    let res = client
+        .request(
+            reqwest::Method::POST,
+            "students",
+            Some(json!({"name": "A"})),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res.status, 201);
+    assert_eq!(res.body["id"], 1);
+
+    let res = client
+        .request(reqwest::Method::GET, "students/1", None)
+        .await
+        .unwrap();
+    assert!(res.is_success());
+    assert_eq!(res.into_result().unwrap()["name"], "A");
+
+    let res = client
+        .request(reqwest::Method::GET, "/students/99", None)
+        .await
+        .unwrap();
+    assert_eq!(res.status, 404);
+    let err = res.into_result().unwrap_err().to_string();
+    assert!(err.starts_with("404"), "{}", err);
+
+    // Empty bodies come back as null
+    let res = client
+        .request(reqwest::Method::OPTIONS, "students", None)
+        .await
+        .unwrap();
+    assert_eq!(res.status, 204);
+    assert!(res.body.is_null());
+}
+
+#[tokio::test]
+async fn test_malformed_json_gets_json_error() {
+    let port = start_test_server("test_bad_json_db").await;
+    let http = reqwest::Client::new();
+    let url = format!("http://localhost:{}/students", port);
+
+    let res = http
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .body("{\"name\": ")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 400);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert!(
+        body["error"].as_str().unwrap().contains("parse"),
+        "{}",
+        body
+    );
+
+    let res = http
+        .put(format!("{}/1", url))
+        .body("{\"name\": \"A\"}")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 415);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert!(body["error"].is_string());
+}
+
+#[tokio::test]
+async fn test_route_alias() {
+    let mut db = EasyDB::init_in_memory("test_route_alias_db").unwrap();
+    db.create_table("student_records_2024", "id INTEGER PRIMARY KEY, name TEXT")
+        .unwrap();
+    db.route_alias("student_records_2024", "students").unwrap();
+    assert!(db.route_alias("student_records_2024", "_students").is_err());
+    assert!(db.route_alias("student_records_2024", "a/b").is_err());
+    let port = serve(db).await;
+
+    let client = EasyClient::new("localhost", port);
+    let res = client.post("students", json!({"name": "A"})).await.unwrap();
+    assert_eq!(res["id"], 1);
+    let row = client.get_by_id("students", 1, None).await.unwrap();
+    assert_eq!(row["name"], "A");
+
+    // The physical name is not a route
+    let res = reqwest::get(format!("http://localhost:{}/student_records_2024", port))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["available"], json!(["students"]));
+
+    // Two tables can't share a route
+    let mut clash = EasyDB::init_in_memory("test_route_alias_clash_db").unwrap();
+    clash
+        .create_table("old_students", "id INTEGER PRIMARY KEY")
+        .unwrap();
+    clash
+        .create_table("students", "id INTEGER PRIMARY KEY")
+        .unwrap();
+    clash.route_alias("old_students", "students").unwrap();
+    assert!(clash.bind(0).await.is_err());
+}
+
+#[tokio::test]
+async fn test_typed_values() {
+    let port = start_test_server("test_typed_db").await;
+    let client = EasyClient::new("localhost", port);
+    client
+        .post("students", json!({"name": "A", "age": 20, "gpa": 3.5}))
+        .await
+        .unwrap();
+
+    let rows = client
+        .query("students", &Query::new().param("_typed", true))
+        .await
+        .unwrap();
+    assert_eq!(rows[0]["gpa"], json!({"value": 3.5, "type": "REAL"}));
+    assert_eq!(rows[0]["age"], json!({"value": 20, "type": "INTEGER"}));
+    assert_eq!(rows[0]["name"], json!({"value": "A", "type": "TEXT"}));
+
+    let mut params = HashMap::new();
+    params.insert("_typed", "true");
+    let row = client.get_by_id("students", 1, Some(params)).await.unwrap();
+    assert_eq!(row["id"], json!({"value": 1, "type": "INTEGER"}));
+
+    // The default stays flat
+    let row = client.get_by_id("students", 1, None).await.unwrap();
+    assert_eq!(row["gpa"], 3.5);
+}
+
+#[tokio::test]
+async fn test_backup_of_live_wal_database() {
+    let root = temp_dir("backup");
+    let dir = root.join("backups");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let config = DbConfig {
+        wal: true,
+        ..Default::default()
+    };
+    let mut db = EasyDB::builder("logs")
+        .path(root.join("logs.db"))
+        .config(config)
+        .build()
+        .unwrap()
+        .with_api_key("admin")
+        .with_backup_endpoint(&dir);
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .unwrap();
+    db.insert("logs", &json!({"message": "before start"}))
+        .unwrap();
+    db.backup_to(dir.join("manual.db")).unwrap();
+    let port = serve(db).await;
+
+    let count = |path: &std::path::Path| -> i64 {
+        rusqlite::Connection::open(path)
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM logs", [], |r| r.get(0))
+            .unwrap()
+    };
+    assert_eq!(count(&dir.join("manual.db")), 1);
+
+    // Writes still sitting in the WAL are part of the snapshot
+    let client = EasyClient::new("localhost", port).with_api_key("admin");
+    client
+        .post("logs", json!({"message": "while running"}))
+        .await
+        .unwrap();
+
+    let http = reqwest::Client::new();
+    let url = format!("http://localhost:{}/_backup", port);
+    let res = http.post(&url).send().await.unwrap();
+    assert_eq!(res.status(), 401);
+    let res = http
+        .post(&url)
+        .header("X-API-Key", "admin")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    let path = std::path::PathBuf::from(body["path"].as_str().unwrap());
+    assert!(path.starts_with(&dir));
+    assert_eq!(count(&path), 2);
+
+    // The endpoint refuses to run unprotected
+    let open = EasyDB::init_in_memory("test_backup_open_db")
+        .unwrap()
+        .with_backup_endpoint(&dir);
+    assert!(open.bind(0).await.is_err());
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_vacuum_shrinks_the_file() {
+    let dir = temp_dir("vacuum");
+    let path = dir.join("logs.db");
+    let mut db = EasyDB::init_at(&path).unwrap();
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .unwrap();
+    let message = "x".repeat(1000);
+    db.with_connection(|conn| {
+        for _ in 0..500 {
+            conn.execute("INSERT INTO logs (message) VALUES (?)", [&message])?;
+        }
+        conn.execute("DELETE FROM logs", [])
+    })
+    .unwrap();
+
+    let size = || std::fs::metadata(&path).unwrap().len();
+    let before = size();
+    db.vacuum().unwrap();
+    assert!(size() < before / 2, "{} -> {}", before, size());
+    db.analyze().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_repeated_keys_match_any_value() {
+    let mut db = EasyDB::init_in_memory("test_repeated_keys_db").unwrap();
+    db.create_table(
+        "students",
+        "id INTEGER PRIMARY KEY, name TEXT, class_grade TEXT, age INTEGER",
+    )
+    .unwrap();
+    let port = serve(db).await;
+    let client = EasyClient::new("localhost", port);
+
+    for (name, grade, age) in [
+        ("Ali", "10-A", 16),
+        ("Ayse", "11-B", 17),
+        ("Can", "12-C", 18),
+        ("Deniz", "A,B", 70),
+    ] {
+        client
+            .post(
+                "students",
+                json!({"name": name, "class_grade": grade, "age": age}),
+            )
+            .await
+            .unwrap();
+    }
+
+    let names = |body: serde_json::Value| -> Vec<String> {
+        let mut names: Vec<String> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["name"].as_str().unwrap().to_string())
+            .collect();
+        names.sort();
+        names
+    };
+    let http = reqwest::Client::new();
+    let url = format!("http://localhost:{}/students", port);
+    let get = |query: &'static str| {
+        let http = http.clone();
+        let url = url.clone();
+        async move {
+            let res = http.get(format!("{}?{}", url, query)).send().await.unwrap();
+            assert_eq!(res.status(), 200);
+            res.json::<serde_json::Value>().await.unwrap()
+        }
+    };
+
+    let body = get("class_grade=10-A&class_grade=11-B").await;
+    assert_eq!(names(body), vec!["Ali", "Ayse"]);
+
+    // Pagination links keep every value
+    let res = http
+        .get(format!(
+            "{}?class_grade=10-A&class_grade=11-B&_per_page=1",
+            url
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.headers()["x-total-count"], "2");
+    let link = res.headers()["link"].to_str().unwrap();
+    assert!(
+        link.contains(
+            "</students?_page=2&_per_page=1&class_grade=10-A&class_grade=11-B>; rel=\"next\""
+        ),
+        "{}",
+        link
+    );
+
+    // Unlike `__in`, a repeated key keeps commas inside values
+    let body = get("class_grade=A%2CB&class_grade=12-C").await;
+    assert_eq!(names(body), vec!["Can", "Deniz"]);
+
+    // Other operators are OR-ed, and still AND-ed with the remaining filters
+    let body = get("name_ci=ali&name_ci=DENIZ").await;
+    assert_eq!(names(body), vec!["Ali", "Deniz"]);
+    let body = get("name_ci=ali&name_ci=DENIZ&age_gt=65").await;
+    assert_eq!(names(body), vec!["Deniz"]);
+
+    // Bulk writes select the same rows
+    let res = http
+        .delete(format!("{}?class_grade=10-A&class_grade=12-C", url))
+        .header("X-Confirm-Bulk", "true")
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["deleted"], 2);
+    let rows = client.get("students", None).await.unwrap();
+    assert_eq!(names(rows), vec!["Ayse", "Deniz"]);
+}
+
+#[tokio::test]
+async fn test_waiting_query_does_not_stall_the_server() {
+    let dir = temp_dir("blocking_pool");
+    let path = dir.join("logs.db");
+    let mut db = EasyDB::init_at(&path).unwrap();
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .unwrap();
+    let port = serve(db).await;
+
+    // Another process holds the write lock, so the POST sits in SQLite's busy handler
+    let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+    let blocker = std::thread::spawn({
+        let path = path.clone();
+        move || {
+            let conn = rusqlite::Connection::open(path).unwrap();
+            conn.execute_batch("BEGIN IMMEDIATE").unwrap();
+            locked_tx.send(()).unwrap();
+            std::thread::sleep(Duration::from_millis(1000));
+            conn.execute_batch("COMMIT").unwrap();
+        }
+    });
+    locked_rx.recv().unwrap();
+
+    let url = format!("http://localhost:{}/logs", port);
+    let http = reqwest::Client::new();
+    let write = tokio::spawn({
+        let (http, url) = (http.clone(), url.clone());
+        async move {
+            http.post(&url)
+                .json(&json!({"message": "waits"}))
+                .send()
+                .await
+                .unwrap()
+                .status()
+        }
+    });
+    sleep(Duration::from_millis(100)).await;
+
+    // The test runtime has a single thread: it only answers if the POST isn't blocking it
+    let res = tokio::time::timeout(
+        Duration::from_millis(500),
+        http.request(reqwest::Method::OPTIONS, &url).send(),
+    )
+    .await
+    .expect("server stalled behind the waiting query")
+    .unwrap();
+    assert_eq!(res.status(), 204);
+    assert!(!write.is_finished());
+
+    assert_eq!(write.await.unwrap(), 201);
+    blocker.join().unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_ping_and_wait_until_ready() {
+    // A port nobody listens on (yet)
+    let port = std::net::TcpListener::bind(("127.0.0.1", 0))
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let client = EasyClient::new("localhost", port);
+
+    // Nothing listens yet
+    assert!(!client.ping().await.unwrap());
+    assert!(client
+        .wait_until_ready(Duration::from_millis(100))
+        .await
+        .is_err());
+
+    let db = EasyDB::init_in_memory("test_ping_db").unwrap();
+    tokio::spawn(async move {
+        sleep(Duration::from_millis(200)).await;
+        db.run_server(port).await.unwrap()
+    });
+    client
+        .wait_until_ready(Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert!(client.ping().await.unwrap());
+}
+
+#[tokio::test]
+async fn test_writes_report_affected_rows() {
+    let mut db = EasyDB::init_in_memory("test_affected_db").unwrap();
+    db.create_table(
+        "logs",
+        "id INTEGER PRIMARY KEY, code TEXT UNIQUE, level TEXT",
+    )
+    .unwrap();
+    let port = serve(db).await;
+    let client = EasyClient::new("localhost", port);
+
+    for (code, level) in [("a", "info"), ("b", "info"), ("c", "warn")] {
+        let res = client
+            .post("logs", json!({"code": code, "level": level}))
+            .await
+            .unwrap();
+        assert_eq!(res["affected"], 1);
+    }
+    let res = client
+        .put("logs", 1, json!({"level": "debug"}))
+        .await
+        .unwrap();
+    assert_eq!(res["affected"], 1);
+    let res = client
+        .patch("logs", 1, json!({"level": "info"}))
+        .await
+        .unwrap();
+    assert_eq!(res["affected"], 1);
+    let res = client.delete("logs", 3).await.unwrap();
+    assert_eq!(res["affected"], 1);
+
+    let http = reqwest::Client::new();
+    let url = format!("http://localhost:{}/logs", port);
+    let res = http
+        .post(format!("{}/upsert?conflict=code", url))
+        .json(&json!({"code": "a"}))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["affected"], 0);
+
+    let res = http
+        .put(format!("{}?level=info", url))
+        .header("X-Confirm-Bulk", "true")
+        .json(&json!({"level": "warn"}))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(
+        (body["updated"].clone(), body["affected"].clone()),
+        (json!(2), json!(2))
+    );
+
+    let res = http
+        .delete(format!("{}?level=warn", url))
+        .header("X-Confirm-Bulk", "true")
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["affected"], 2);
+}
+
+#[tokio::test]
+async fn test_default_sort_applies_without_sort_param() {
+    let mut db = EasyDB::init_in_memory("test_default_sort_db").unwrap();
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .unwrap();
+    assert!(db.default_sort("logs", "id", "newest").is_err());
+    db.default_sort("logs", "id", "desc").unwrap();
+    let port = serve(db).await;
+    let client = EasyClient::new("localhost", port);
+
+    for message in ["b", "a", "c"] {
+        client
+            .post("logs", json!({"message": message}))
+            .await
+            .unwrap();
+    }
+    let listed = |query: Query| {
+        let client = &client;
+        async move {
+            let rows = client.query("logs", &query).await.unwrap();
+            rows.as_array()
+                .unwrap()
+                .iter()
+                .map(|r| r["message"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    assert_eq!(listed(Query::new()).await, vec!["c", "a", "b"]);
+    assert_eq!(listed(Query::new().limit(1)).await, vec!["c"]);
+    assert_eq!(
+        listed(Query::new().param("_order", "asc")).await,
+        vec!["b", "a", "c"]
+    );
+    // An explicit sort replaces the default
+    assert_eq!(
+        listed(Query::new().sort("message", Order::Asc)).await,
+        vec!["a", "b", "c"]
+    );
+
+    // The column must exist
+    let mut db = EasyDB::init_in_memory("test_default_sort_missing_db").unwrap();
+    db.create_table("logs", "id INTEGER PRIMARY KEY").unwrap();
+    db.default_sort("logs", "created_at", "desc").unwrap();
+    assert!(db.bind(0).await.is_err());
+}
+
+#[tokio::test]
+async fn test_q_searches_every_text_column() {
+    let mut db = EasyDB::init_in_memory("test_q_db").unwrap();
+    db.create_table(
+        "users",
+        "id INTEGER PRIMARY KEY, name TEXT, email VARCHAR(80), age INTEGER, secret TEXT",
+    )
+    .unwrap();
+    db.hide_column("users", "secret").unwrap();
+    let port = serve(db).await;
+    let client = EasyClient::new("localhost", port);
+
+    for (name, email, age, secret) in [
+        ("Ali", "ali@example.com", 20, "x"),
+        ("Veli", "v@ALIcorp.com", 30, "x"),
+        ("Ayse", "ayse@example.com", 40, "ali"),
+        ("Can", "100%_sure@example.com", 12, "x"),
+    ] {
+        client
+            .post(
+                "users",
+                json!({"name": name, "email": email, "age": age, "secret": secret}),
+            )
+            .await
+            .unwrap();
+    }
+    let names = |query: Query| {
+        let client = &client;
+        async move {
+            let rows = client.query("users", &query).await.unwrap();
+            let mut names: Vec<String> = rows
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|r| r["name"].as_str().unwrap().to_string())
+                .collect();
+            names.sort();
+            names
+        }
+    };
+
+    // Hidden columns and numbers are not searched
+    assert_eq!(
+        names(Query::new().param("_q", "ali")).await,
+        vec!["Ali", "Veli"]
+    );
+    assert!(names(Query::new().param("_q", "12")).await.is_empty());
+    assert_eq!(
+        names(Query::new().param("_q", "ali").gt("age", 25)).await,
+        vec!["Veli"]
+    );
+    // Wildcards are literal
+    assert_eq!(names(Query::new().param("_q", "0%_s")).await, vec!["Can"]);
+    assert!(names(Query::new().param("_q", "a_i")).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_conditional_get_with_etag() {
+    let port = start_test_server("test_etag_db").await;
+    let client = EasyClient::new("localhost", port);
+    client
+        .post("students", json!({"name": "Ali", "age": 20, "gpa": 3.1}))
+        .await
+        .unwrap();
+
+    let http = reqwest::Client::new();
+    for path in ["students", "students/1"] {
+        let url = format!("http://localhost:{}/{}", port, path);
+        let res = http.get(&url).send().await.unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()["content-type"], "application/json");
+        let etag = res.headers()["etag"].to_str().unwrap().to_string();
+        assert!(etag.starts_with("W/\""), "{}", etag);
+
+        // Unchanged data: no body
+        let res = http
+            .get(&url)
+            .header("If-None-Match", &etag)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 304);
+        assert_eq!(res.headers()["etag"], etag.as_str());
+        assert!(res.text().await.unwrap().is_empty());
+
+        let res = http
+            .get(&url)
+            .header("If-None-Match", "\"other\"")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), 200);
+    }
+
+    // A write changes the tag
+    let url = format!("http://localhost:{}/students/1", port);
+    let etag = http.get(&url).send().await.unwrap().headers()["etag"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    client.put("students", 1, json!({"age": 21})).await.unwrap();
+    let res = http
+        .get(&url)
+        .header("If-None-Match", &etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    assert_ne!(res.headers()["etag"], etag.as_str());
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["age"], 21);
+
+    // Errors carry no tag
+    let res = http
+        .get(format!("http://localhost:{}/students/99", port))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 404);
+    assert!(!res.headers().contains_key("etag"));
+}
+
+#[tokio::test]
+async fn test_custom_routes_are_served_with_generated_ones() {
+    let mut db = EasyDB::init_in_memory("test_custom_routes_db")
+        .unwrap()
+        .with_prefix("/api")
+        .unwrap()
+        .with_api_key("secret")
+        .with_route(
+            "/report",
+            easy_db::axum::routing::get(|| async { easy_db::axum::Json(json!({"report": "ok"})) }),
+        )
+        .with_route(
+            "/login",
+            easy_db::axum::routing::post(|| async { "welcome" }),
+        );
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .unwrap();
+    let port = serve(db).await;
+
+    let http = reqwest::Client::new();
+    let base = format!("http://localhost:{}/api", port);
+    // Behind the same auth as the tables
+    let res = http.get(format!("{}/report", base)).send().await.unwrap();
+    assert_eq!(res.status(), 401);
+    let res = http
+        .get(format!("{}/report", base))
+        .header("X-API-Key", "secret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: serde_json::Value = res.json().await.unwrap();
+    assert_eq!(body["report"], "ok");
+    let res = http
+        .post(format!("{}/login", base))
+        .header("X-API-Key", "secret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.text().await.unwrap(), "welcome");
+
+    let client = EasyClient::new("localhost", port)
+        .with_prefix("/api")
+        .with_api_key("secret");
+    let rows = client.get("logs", None).await.unwrap();
+    assert_eq!(rows, json!([]));
+}
+
+#[tokio::test]
+async fn test_overlapping_custom_routes_are_rejected() {
+    let hello = || easy_db::axum::routing::get(|| async { "hello" });
+    for path in [
+        "/logs",
+        "/logs/{id}/extra",
+        "/_health",
+        "/library/books",
+        "/{any}",
+        "/report",
+        "report",
+    ] {
+        let mut db = EasyDB::init_in_memory("test_route_overlap_db")
+            .unwrap()
+            .with_route("/report", hello());
+        db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+            .unwrap();
+        let library = EasyDB::init_in_memory("test_route_library_db").unwrap();
+        let db = db
+            .mount("/library", library)
+            .unwrap()
+            .with_route(path, hello());
+        assert!(db.into_router().is_err(), "{}", path);
+    }
+
+    let db = EasyDB::init_in_memory("test_route_free_db")
+        .unwrap()
+        .with_route("/report", hello())
+        .with_route("/report/daily", hello());
+    assert!(db.into_router().is_ok());
+}
+
+#[tokio::test]
+async fn test_into_router_serves_without_a_port() {
+    use easy_db::axum::body::{to_bytes, Body};
+    use easy_db::axum::http::Request;
+    use tower::ServiceExt;
+
+    let mut db = EasyDB::init_in_memory("test_into_router_db")
+        .unwrap()
+        .with_api_key("secret");
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .unwrap();
+    let (app, _queues) = db.into_router().unwrap();
+
+    let post = Request::post("/logs")
+        .header("content-type", "application/json")
+        .header("x-api-key", "secret")
+        .body(Body::from(r#"{"message": "hello"}"#))
+        .unwrap();
+    let res = app.clone().oneshot(post).await.unwrap();
+    assert_eq!(res.status(), 201);
+    assert_eq!(res.headers()["location"], "/logs/1");
+
+    // Middleware is part of the router
+    let res = app
+        .clone()
+        .oneshot(Request::get("/logs").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 401);
+
+    let get = Request::get("/logs")
+        .header("x-api-key", "secret")
+        .body(Body::empty())
+        .unwrap();
+    let res = app.oneshot(get).await.unwrap();
+    assert_eq!(res.status(), 200);
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let rows: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(rows, json!([{"id": 1, "message": "hello"}]));
+
+    // Startup errors surface here too
+    let mut db = EasyDB::init_in_memory("test_into_router_bad_db").unwrap();
+    db.create_table("logs", "id INTEGER PRIMARY KEY").unwrap();
+    db.primary_key("logs", "code").unwrap();
+    assert!(db.into_router().is_err());
+}
+
+#[tokio::test]
+async fn test_into_router_flushes_batched_writes() {
+    use easy_db::axum::body::Body;
+    use easy_db::axum::http::Request;
+    use tower::ServiceExt;
+
+    let dir = temp_dir("router_batch");
+    let path = dir.join("logs.db");
+    let mut db = EasyDB::init_at(&path).unwrap();
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .unwrap();
+    db.batch_writes("logs", 25, Duration::from_secs(60))
+        .unwrap();
+    let (app, queues) = db.into_router().unwrap();
+
+    for i in 0..3 {
+        let post = Request::post("/logs")
+            .header("content-type", "application/json")
+            .body(Body::from(format!(r#"{{"message": "entry {}"}}"#, i)))
+            .unwrap();
+        let res = app.clone().oneshot(post).await.unwrap();
+        assert_eq!(res.status(), 202);
+    }
+
+    // The partial batch is written once the router is gone
+    drop(app);
+    tokio::time::timeout(Duration::from_secs(2), queues.flush())
+        .await
+        .expect("Queues were not flushed");
+    let stored: i64 = rusqlite::Connection::open(&path)
+        .unwrap()
+        .query_row("SELECT COUNT(*) FROM logs", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(stored, 3);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_delete_can_return_no_content() {
+    let mut db = EasyDB::init_in_memory("test_no_content_db")
+        .unwrap()
+        .with_delete_no_content(true);
+    db.create_table("logs", "id INTEGER PRIMARY KEY, message TEXT")
+        .unwrap();
+    let port = serve(db).await;
+    let client = EasyClient::new("localhost", port);
+    client.post("logs", json!({"message": "a"})).await.unwrap();
+    client.post("logs", json!({"message": "b"})).await.unwrap();
+
+    let res = reqwest::Client::new()
+        .delete(format!("http://localhost:{}/logs/1", port))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 204);
+    assert!(res.text().await.unwrap().is_empty());
+
+    assert_eq!(
+        client.delete("logs", 2).await.unwrap(),
+        serde_json::Value::Null
+    );
+    let missing = client
+        .request(reqwest::Method::DELETE, "logs/2", None)
+        .await
+        .unwrap();
+    assert_eq!(missing.status, 404);
+    assert_eq!(missing.body["error"], "Record not found");
+}